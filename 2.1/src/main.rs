@@ -1,5 +1,11 @@
+mod parse;
+mod scan;
+
 use std::io::Read as _;
 
+use parse::{parse_range, parse_ranges, ParseError};
+use scan::{scan_ranges, ScanOptions};
+
 #[derive(Debug)]
 struct Range {
     start: u64,
@@ -7,38 +13,42 @@ struct Range {
 }
 
 impl std::str::FromStr for Range {
-    type Err = std::num::ParseIntError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (start, end) = s.split_once('-').unwrap();
-        Ok(Range { start: start.parse::<u64>()?, end: end.parse::<u64>()? })
+        parse_range(s, 0)
     }
 }
 
+fn parse_args() -> ScanOptions {
+    let mut options = ScanOptions::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--threads" => options.threads = value().parse::<usize>().unwrap(),
+            "--chunk-size" => options.chunk_size = value().parse::<u64>().unwrap(),
+            "--checkpoint" => options.checkpoint = Some(value().into()),
+            _ => panic!("unknown argument: {}", arg),
+        }
+    }
+    options
+}
+
 fn main() {
+    let options = parse_args();
     let mut stdin = std::io::stdin();
     let mut input = String::new();
     let _read_length: usize = stdin.read_to_string(&mut input).unwrap();
-    let mut ranges = Vec::new();
-    for string in input.split(',') {
-        let range = string.parse::<Range>().unwrap();
-        ranges.push(range);
-    }
-    let mut total = 0;
-    for range in ranges {
-        total += search_range(&range);
-    }
-    println!("total: {}", total);
-}
-
- fn search_range(range: &Range) -> u64 {
-    let mut total = 0;
-    for i in range.start..=range.end {
-        if is_invalid(i) {
-            total += i;
+    let ranges = match parse_ranges(&input) {
+        Ok(ranges) => ranges,
+        Err(e) => {
+            eprintln!("invalid range list {}", e);
+            std::process::exit(1);
         }
-    }
-    return total;
+    };
+    let total = scan_ranges(&ranges, "2.1", is_invalid, &options);
+    println!("total: {}", total);
 }
 
 fn is_invalid(id: u64) -> bool {
@@ -56,9 +66,5 @@ fn is_invalid(id: u64) -> bool {
         divisor *= 10;   
     }
 
-    let invalid = id / divisor == id % divisor;
-    if invalid {
-        println!("invalid id: {}", id);
-    }
-    return invalid;
+    id / divisor == id % divisor
 }
\ No newline at end of file
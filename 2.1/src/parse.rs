@@ -0,0 +1,119 @@
+use crate::Range;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn error(offset: usize, message: String) -> ParseError {
+    ParseError { offset, message }
+}
+
+fn parse_number(s: &str, offset: usize) -> Result<u64, ParseError> {
+    if s.is_empty() {
+        return Err(error(offset, "expected a number".to_string()));
+    }
+    if let Some(position) = s.find(|c: char| !c.is_ascii_digit()) {
+        let c = s[position..].chars().next().unwrap();
+        return Err(error(offset + position, format!("unexpected character {:?} in number", c)));
+    }
+    s.parse::<u64>().map_err(|_| error(offset, format!("number {} is too large", s)))
+}
+
+/// Parses a single `start-end` token that begins at `offset` in the input.
+pub fn parse_range(token: &str, offset: usize) -> Result<Range, ParseError> {
+    let Some((start, end)) = token.split_once('-') else {
+        return Err(error(offset, format!("expected start-end, found {:?}", token)));
+    };
+    let end_offset = offset + start.len() + 1;
+    let range = Range { start: parse_number(start, offset)?, end: parse_number(end, end_offset)? };
+    if range.start > range.end {
+        return Err(error(offset, format!("range {} is reversed, start is greater than end", token)));
+    }
+    Ok(range)
+}
+
+/// Parses a list of ranges separated by commas and/or whitespace. Newlines and a trailing
+/// comma are accepted; an empty entry between two commas is not.
+pub fn parse_ranges(input: &str) -> Result<Vec<Range>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut ranges = Vec::new();
+    let mut entry_since_comma = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b if b.is_ascii_whitespace() => i += 1,
+            b',' => {
+                if !entry_since_comma {
+                    return Err(error(i, "empty entry before ','".to_string()));
+                }
+                entry_since_comma = false;
+                i += 1;
+            }
+            _ => {
+                let start = i;
+                while i < bytes.len() && bytes[i] != b',' && !bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                ranges.push(parse_range(&input[start..i], start)?);
+                entry_since_comma = true;
+            }
+        }
+    }
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_ranges, ParseError};
+
+    fn bounds(input: &str) -> Result<Vec<(u64, u64)>, ParseError> {
+        parse_ranges(input).map(|ranges| ranges.iter().map(|r| (r.start, r.end)).collect())
+    }
+
+    fn error_offset(input: &str) -> usize {
+        bounds(input).unwrap_err().offset
+    }
+
+    #[test]
+    fn accepts_the_layouts_of_a_range_list() {
+        assert_eq!(bounds("11-22,95-115\n"), Ok(vec![(11, 22), (95, 115)]));
+        assert_eq!(bounds("11-22,95-115,"), Ok(vec![(11, 22), (95, 115)]));
+        assert_eq!(bounds("11-22,\n95-115\n,998-1012\n"), Ok(vec![(11, 22), (95, 115), (998, 1012)]));
+        assert_eq!(bounds("11-22 95-115\n\n998-1012"), Ok(vec![(11, 22), (95, 115), (998, 1012)]));
+        assert_eq!(bounds("7-7"), Ok(vec![(7, 7)]));
+        assert_eq!(bounds(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn rejects_an_empty_entry() {
+        let error = bounds("11-22,,95-115").unwrap_err();
+        assert_eq!(error, ParseError { offset: 6, message: "empty entry before ','".to_string() });
+        assert_eq!(error_offset(",11-22"), 0);
+    }
+
+    #[test]
+    fn rejects_a_reversed_range_at_its_start() {
+        assert_eq!(error_offset("11-22,30-20"), 6);
+    }
+
+    #[test]
+    fn points_at_the_bad_byte() {
+        // Bad characters in the start and in the end of the third range, which begins at 13.
+        assert_eq!(error_offset("11-22,95-115,9x8-1012"), 14);
+        assert_eq!(error_offset("11-22,95-115,998-10a2"), 19);
+        // A missing dash, or a missing end, is reported where the token or the end begins.
+        assert_eq!(error_offset("11-22,95-115,998"), 13);
+        assert_eq!(error_offset("11-22,95-115,998-"), 17);
+        assert_eq!(error_offset("11-22\n95-115\n998-99999999999999999999"), 17);
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::Range;
+
+#[derive(Debug)]
+pub struct ScanOptions {
+    pub threads: usize,
+    pub chunk_size: u64,
+    pub checkpoint: Option<PathBuf>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        ScanOptions { threads, chunk_size: 1_000_000, checkpoint: None }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Chunk {
+    start: u64,
+    end: u64,
+}
+
+fn split_into_chunks(ranges: &[Range], chunk_size: u64) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    for range in ranges {
+        let mut start = range.start;
+        while start <= range.end {
+            let end = start.saturating_add(chunk_size - 1).min(range.end);
+            chunks.push(Chunk { start, end });
+            if end == u64::MAX {
+                break;
+            }
+            start = end + 1;
+        }
+    }
+    chunks
+}
+
+// FNV-1a over the rule name and the chunk bounds, so a checkpoint is only reused for the same
+// rule, ranges and chunk size.
+fn fingerprint(rule: &str, chunks: &[Chunk]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    let bounds = chunks.iter().flat_map(|chunk| [chunk.start.to_le_bytes(), chunk.end.to_le_bytes()]).flatten();
+    for byte in rule.bytes().chain([0]).chain(bounds) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Chunk totals recorded in the checkpoint at `path`, and the length of the file up to its
+/// last complete line, which is where new records go.
+fn load_checkpoint(path: &PathBuf, fingerprint: u64) -> (HashMap<usize, u64>, u64) {
+    let mut done = HashMap::new();
+    let Ok(contents) = std::fs::read(path) else {
+        return (done, 0);
+    };
+    // Only newline-terminated lines were written in full. A record cut short by an
+    // interruption is dropped here and cut off the file, so its chunk is simply rescanned.
+    let complete = contents.iter().rposition(|&b| b == b'\n').map_or(0, |last| last + 1);
+    let mut lines = contents[..complete].split(|&b| b == b'\n').map(String::from_utf8_lossy);
+    if lines.next().as_deref() != Some(format!("fingerprint {:016x}", fingerprint).as_str()) {
+        eprintln!("checkpoint {} does not match this rule and these ranges, starting over", path.display());
+        return (done, 0);
+    }
+    for line in lines {
+        let Some((index, total)) = line.split_once(' ') else {
+            continue;
+        };
+        if let (Ok(index), Ok(total)) = (index.parse::<usize>(), total.parse::<u64>()) {
+            done.insert(index, total);
+        }
+    }
+    (done, complete as u64)
+}
+
+/// Opens the checkpoint for new records: after its last complete line when resuming,
+/// otherwise as a fresh file with just the fingerprint.
+fn open_checkpoint(path: &PathBuf, fingerprint: u64, resume_at: Option<u64>) -> std::io::Result<File> {
+    if let Some(length) = resume_at {
+        let file = OpenOptions::new().append(true).open(path)?;
+        file.set_len(length)?;
+        return Ok(file);
+    }
+    let mut file = File::create(path)?;
+    file.write_all(format!("fingerprint {:016x}\n", fingerprint).as_bytes())?;
+    Ok(file)
+}
+
+/// Sums the IDs in `ranges` for which `is_invalid` holds. `rule` names `is_invalid` in the
+/// checkpoint, so that one rule never resumes from another rule's chunk totals.
+pub fn scan_ranges(ranges: &[Range], rule: &str, is_invalid: fn(u64) -> bool, options: &ScanOptions) -> u64 {
+    let chunks = split_into_chunks(ranges, options.chunk_size.max(1));
+    let fingerprint = fingerprint(rule, &chunks);
+
+    let (done, complete_length) = match &options.checkpoint {
+        Some(path) => load_checkpoint(path, fingerprint),
+        None => (HashMap::new(), 0),
+    };
+    if !done.is_empty() {
+        eprintln!("resuming: {} of {} chunks already scanned", done.len(), chunks.len());
+    }
+    let checkpoint = options.checkpoint.as_ref().map(|path| {
+        let resume_at = (!done.is_empty()).then_some(complete_length);
+        Mutex::new(open_checkpoint(path, fingerprint, resume_at).unwrap())
+    });
+
+    let mut totals: Vec<Option<u64>> = (0..chunks.len()).map(|i| done.get(&i).copied()).collect();
+    let pending = totals.iter().enumerate().filter(|(_, t)| t.is_none()).map(|(i, _)| i).collect::<Vec<usize>>();
+    let next = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(chunks.len() - pending.len());
+    let results = Mutex::new(Vec::new());
+
+    std::thread::scope(|scope| {
+        for _ in 0..options.threads.max(1) {
+            scope.spawn(|| {
+                while let Some(&index) = pending.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let chunk = chunks[index];
+                    let total = (chunk.start..=chunk.end).filter(|id| is_invalid(*id)).sum::<u64>();
+                    if let Some(checkpoint) = &checkpoint {
+                        let mut file = checkpoint.lock().unwrap();
+                        // One write per record, so an interruption can only cut off its tail.
+                        file.write_all(format!("{} {}\n", index, total).as_bytes()).unwrap();
+                    }
+                    results.lock().unwrap().push((index, total));
+                    let finished = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                    eprintln!(
+                        "progress: {}/{} chunks ({:.1}%)",
+                        finished,
+                        chunks.len(),
+                        100.0 * finished as f64 / chunks.len() as f64
+                    );
+                }
+            });
+        }
+    });
+
+    for (index, total) in results.into_inner().unwrap() {
+        totals[index] = Some(total);
+    }
+    // Summed in chunk order so the result does not depend on which thread finished first.
+    let total = totals.iter().map(|t| t.unwrap()).sum();
+
+    if let Some(path) = &options.checkpoint {
+        std::fs::remove_file(path).unwrap();
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fingerprint, scan_ranges, split_into_chunks, ScanOptions};
+    use crate::Range;
+
+    fn is_multiple_of_seven(id: u64) -> bool {
+        id.is_multiple_of(7)
+    }
+
+    fn checkpointed(name: &str) -> ScanOptions {
+        let path = std::env::temp_dir().join(format!("scan-{}-{}.checkpoint", std::process::id(), name));
+        ScanOptions { threads: 2, chunk_size: 10, checkpoint: Some(path) }
+    }
+
+    fn sevens(start: u64, end: u64) -> u64 {
+        (start..=end).filter(|&id| is_multiple_of_seven(id)).sum()
+    }
+
+    #[test]
+    fn resumes_from_complete_records_and_rescans_a_torn_one() {
+        let ranges = [Range { start: 1, end: 100 }];
+        let options = checkpointed("torn");
+        let path = options.checkpoint.clone().unwrap();
+        let fingerprint = fingerprint("multiples of 7", &split_into_chunks(&ranges, 10));
+        // Chunk 0 (1-10) holds 7 and chunk 1 (11-20) holds 14. Chunk 0's record is complete
+        // but deliberately off by 1000, so using it shows that the checkpoint was resumed;
+        // chunk 1's record was cut off after "1 1" and must not be read as a total of 1.
+        let contents = format!("fingerprint {:016x}\n0 {}\n1 1", fingerprint, sevens(1, 10) + 1000);
+        std::fs::write(&path, contents).unwrap();
+        let fresh_options = ScanOptions { checkpoint: None, ..checkpointed("fresh") };
+        let fresh = scan_ranges(&ranges, "multiples of 7", is_multiple_of_seven, &fresh_options);
+        let resumed = scan_ranges(&ranges, "multiples of 7", is_multiple_of_seven, &options);
+        assert_eq!(fresh, sevens(1, 100));
+        assert_eq!(resumed, fresh + 1000);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn ignores_a_checkpoint_from_another_rule() {
+        let ranges = [Range { start: 1, end: 100 }];
+        let options = checkpointed("other-rule");
+        let path = options.checkpoint.clone().unwrap();
+        let fingerprint = fingerprint("another rule", &split_into_chunks(&ranges, 10));
+        let records = (0..10).map(|index| format!("{} 1000\n", index)).collect::<String>();
+        std::fs::write(&path, format!("fingerprint {:016x}\n{}", fingerprint, records)).unwrap();
+        assert_eq!(scan_ranges(&ranges, "multiples of 7", is_multiple_of_seven, &options), sevens(1, 100));
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Objective {
+    #[default]
+    Maximize,
+    Minimize,
+}
+
+impl Objective {
+    /// Whether digit `a` is strictly preferable to digit `b`.
+    pub fn prefers(&self, a: u64, b: u64) -> bool {
+        match self {
+            Objective::Maximize => a > b,
+            Objective::Minimize => a < b,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    pub objective: Objective,
+    pub nonzero_first: bool,
+    /// Largest allowed distance between consecutive chosen positions.
+    pub max_gap: Option<usize>,
+    pub forbidden: HashSet<usize>,
+}
+
+impl Constraints {
+    /// The stack greedy is only optimal when any allowed battery may follow any other.
+    pub fn allows_greedy(&self) -> bool {
+        self.max_gap.is_none() && !self.nonzero_first
+    }
+}
+
+/// Optimal selection under arbitrary constraints. `feasible[r][i]` records whether `r + 1`
+/// batteries can be chosen starting at position `i`; the answer is then built digit by digit,
+/// keeping every position that ties for the best prefix so far, since which of them is used
+/// decides what can come next. Runs in O(n·k) time and memory.
+pub fn select_dp(batteries: &[u64], k: usize, constraints: &Constraints) -> Option<Vec<usize>> {
+    let n = batteries.len();
+    if k == 0 {
+        return Some(Vec::new());
+    }
+    // A gap of zero leaves no room for a second battery, so only `k = 1` can be met.
+    let gap = constraints.max_gap.unwrap_or(n);
+    let allowed = (0..n).map(|i| !constraints.forbidden.contains(&i)).collect::<Vec<bool>>();
+
+    let mut feasible = vec![allowed.clone()];
+    for r in 1..k {
+        let previous = &feasible[r - 1];
+        // suffix[i] counts feasible positions at or after i, for O(1) window queries.
+        let mut suffix = vec![0usize; n + 1];
+        for i in (0..n).rev() {
+            suffix[i] = suffix[i + 1] + previous[i] as usize;
+        }
+        let row = (0..n)
+            .map(|i| allowed[i] && suffix[(i + 1).min(n)] > suffix[(i + gap + 1).min(n)])
+            .collect::<Vec<bool>>();
+        feasible.push(row);
+    }
+
+    // parents[t][i] is the position chosen at step t - 1 that position i at step t follows.
+    let mut parents: Vec<Vec<Option<usize>>> = Vec::with_capacity(k);
+    let mut reachable: Vec<Option<Option<usize>>> = vec![Some(None); n];
+    let mut chosen = Vec::new();
+    for t in 0..k {
+        let remaining = k - t - 1;
+        let candidates = (0..n)
+            .filter(|&i| reachable[i].is_some() && feasible[remaining][i])
+            .filter(|&i| !(t == 0 && constraints.nonzero_first && batteries[i] == 0))
+            .collect::<Vec<usize>>();
+        let best = candidates.iter().map(|&i| batteries[i]).reduce(|best, digit| {
+            if constraints.objective.prefers(digit, best) { digit } else { best }
+        })?;
+        chosen = candidates.into_iter().filter(|&i| batteries[i] == best).collect();
+        parents.push(reachable.iter().map(|parent| parent.flatten()).collect());
+
+        let mut last_chosen = None;
+        let mut next_chosen = chosen.iter().peekable();
+        for (i, slot) in reachable.iter_mut().enumerate() {
+            *slot = match last_chosen {
+                Some(p) if i - p <= gap => Some(Some(p)),
+                _ => None,
+            };
+            if next_chosen.next_if_eq(&&i).is_some() {
+                last_chosen = Some(i);
+            }
+        }
+    }
+
+    let mut indices = vec![*chosen.first()?];
+    for t in (1..k).rev() {
+        indices.push(parents[t][*indices.last().unwrap()].unwrap());
+    }
+    indices.reverse();
+    Some(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bank::{select, Bank};
+    use crate::testing::{bank, combinations, digits_at};
+
+    fn meets(batteries: &[u64], indices: &[usize], constraints: &Constraints) -> bool {
+        let gap = constraints.max_gap.unwrap_or(usize::MAX);
+        indices.iter().all(|i| !constraints.forbidden.contains(i))
+            && indices.windows(2).all(|pair| pair[1] - pair[0] <= gap)
+            && !(constraints.nonzero_first && indices.first().is_some_and(|&i| batteries[i] == 0))
+    }
+
+    fn random_constraints(rng: &mut fastrand::Rng, n: usize) -> Constraints {
+        let objective = if rng.bool() { Objective::Maximize } else { Objective::Minimize };
+        let max_gap = match rng.u64(..3) {
+            0 => None,
+            _ => Some(rng.usize(..4)),
+        };
+        let forbidden = (0..n).filter(|_| rng.u64(..5) == 0).collect();
+        Constraints { objective, nonzero_first: rng.bool(), max_gap, forbidden }
+    }
+
+    #[test]
+    fn selection_matches_brute_force() {
+        let mut rng = fastrand::Rng::with_seed(31);
+        for _ in 0..3000 {
+            let digits = rng.u64(1..=10);
+            let batteries = bank(&mut rng, 9, digits);
+            let constraints = random_constraints(&mut rng, batteries.len());
+            let bank = Bank { batteries: batteries.clone() };
+            for k in 0..=batteries.len() {
+                let values = combinations(batteries.len(), k)
+                    .into_iter()
+                    .filter(|choice| meets(&batteries, choice, &constraints))
+                    .map(|choice| digits_at(&batteries, &choice));
+                let best = match constraints.objective {
+                    Objective::Maximize => values.max(),
+                    Objective::Minimize => values.min(),
+                };
+                let selection = select(&bank, k, &constraints);
+                let context = format!("{:?}, k = {}, {:?}", batteries, k, constraints);
+                assert_eq!(selection.is_some(), best.is_some(), "{}", context);
+                if let (Some(selection), Some(best)) = (selection, best) {
+                    assert!(meets(&batteries, &selection.indices, &constraints), "{}", context);
+                    assert!(selection.indices.windows(2).all(|pair| pair[0] < pair[1]), "{}", context);
+                    assert_eq!(digits_at(&batteries, &selection.indices), best, "{}", context);
+                }
+            }
+        }
+    }
+}
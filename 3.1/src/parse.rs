@@ -0,0 +1,109 @@
+use std::io::BufRead;
+
+use crate::bank::Bank;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn describe(byte: u8) -> String {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        format!("{:?}", byte as char)
+    } else {
+        format!("byte 0x{:02x}", byte)
+    }
+}
+
+/// Reads one bank per line straight from the byte stream, so only the bank being parsed is
+/// held in memory. Accepts `\n` and `\r\n` line endings and skips blank lines.
+#[derive(Debug)]
+pub struct BankReader<R> {
+    reader: R,
+    line: usize,
+}
+
+impl<R: BufRead> BankReader<R> {
+    pub fn new(reader: R) -> Self {
+        BankReader { reader, line: 0 }
+    }
+
+    /// Consumes one line, returning its digits and whether the stream ended before a newline.
+    fn read_line(&mut self) -> Result<(Vec<u64>, bool), ParseError> {
+        let line = self.line;
+        let error = |column, message| ParseError { line, column, message };
+        let mut batteries = Vec::new();
+        let mut column = 0;
+        let mut carriage_return = false;
+        let mut first_error = None;
+        loop {
+            let buffer = self
+                .reader
+                .fill_buf()
+                .map_err(|e| error(column, format!("read failed: {}", e)))?;
+            if buffer.is_empty() {
+                return first_error.map_or(Ok((batteries, true)), Err);
+            }
+            let mut consumed = 0;
+            let mut end_of_line = false;
+            for &byte in buffer {
+                consumed += 1;
+                if byte == b'\n' {
+                    end_of_line = true;
+                    break;
+                }
+                column += 1;
+                if first_error.is_some() {
+                    continue;
+                }
+                if carriage_return {
+                    let message = "carriage return not followed by newline".to_string();
+                    first_error = Some(error(column - 1, message));
+                    continue;
+                }
+                match byte {
+                    b'0'..=b'9' => batteries.push((byte - b'0') as u64),
+                    b'\r' => carriage_return = true,
+                    _ => {
+                        let message = format!("expected a digit, found {}", describe(byte));
+                        first_error = Some(error(column, message));
+                    }
+                }
+            }
+            self.reader.consume(consumed);
+            if end_of_line {
+                return first_error.map_or(Ok((batteries, false)), Err);
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for BankReader<R> {
+    type Item = Result<Bank, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line += 1;
+            let (batteries, end_of_input) = match self.read_line() {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            if !batteries.is_empty() {
+                return Some(Ok(Bank { batteries }));
+            }
+            if end_of_input {
+                return None;
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::bank::{Bank, Selection};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Plain,
+    Ansi,
+    Brackets,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Format::Plain),
+            "ansi" => Ok(Format::Ansi),
+            "brackets" => Ok(Format::Brackets),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("Invalid format: {}", s)),
+        }
+    }
+}
+
+fn digits(bank: &Bank) -> String {
+    bank.batteries.iter().map(|battery| battery.to_string()).collect()
+}
+
+/// Renders the bank with the chosen batteries marked, either in bold green or as `[d]`.
+pub fn highlight(bank: &Bank, selection: &Selection, format: Format) -> String {
+    let mut rendered = String::new();
+    let mut chosen = selection.indices.iter().peekable();
+    for (index, battery) in bank.batteries.iter().enumerate() {
+        if chosen.next_if_eq(&&index).is_none() {
+            rendered.push_str(&battery.to_string());
+            continue;
+        }
+        match format {
+            Format::Ansi => rendered.push_str(&format!("\x1b[1;32m{}\x1b[0m", battery)),
+            _ => rendered.push_str(&format!("[{}]", battery)),
+        }
+    }
+    rendered
+}
+
+pub fn to_json(bank: &Bank, selection: &Selection) -> String {
+    let indices = selection.indices.iter().map(|index| index.to_string()).collect::<Vec<String>>();
+    format!(
+        "{{\"bank\": \"{}\", \"indices\": [{}], \"joltage\": {}}}",
+        digits(bank),
+        indices.join(", "),
+        selection.joltage
+    )
+}
@@ -0,0 +1,163 @@
+use std::io::BufRead as _;
+
+use crate::bank::{Bank, Selection};
+use crate::joltage::Joltage;
+use crate::parse::BankReader;
+use crate::render::{highlight, Format};
+
+/// Range maximum over the batteries, breaking ties towards the leftmost position, which is
+/// the battery the greedy selection takes.
+#[derive(Debug)]
+struct SegmentTree {
+    len: usize,
+    /// (digit, position) of the best battery under each node, rooted at 1.
+    nodes: Vec<(u64, usize)>,
+}
+
+fn better(left: (u64, usize), right: (u64, usize)) -> (u64, usize) {
+    if right.0 > left.0 { right } else { left }
+}
+
+impl SegmentTree {
+    fn new(batteries: &[u64]) -> Self {
+        let nodes = vec![(0, 0); 4 * batteries.len().max(1)];
+        let mut tree = SegmentTree { len: batteries.len(), nodes };
+        if !batteries.is_empty() {
+            tree.build(1, 0, batteries.len(), batteries);
+        }
+        tree
+    }
+
+    fn build(&mut self, node: usize, start: usize, end: usize, batteries: &[u64]) {
+        if end - start == 1 {
+            self.nodes[node] = (batteries[start], start);
+            return;
+        }
+        let middle = (start + end) / 2;
+        self.build(2 * node, start, middle, batteries);
+        self.build(2 * node + 1, middle, end, batteries);
+        self.nodes[node] = better(self.nodes[2 * node], self.nodes[2 * node + 1]);
+    }
+
+    fn update(&mut self, position: usize, digit: u64) {
+        self.update_node(1, 0, self.len, position, digit);
+    }
+
+    fn update_node(&mut self, node: usize, start: usize, end: usize, position: usize, digit: u64) {
+        if end - start == 1 {
+            self.nodes[node] = (digit, position);
+            return;
+        }
+        let middle = (start + end) / 2;
+        if position < middle {
+            self.update_node(2 * node, start, middle, position, digit);
+        } else {
+            self.update_node(2 * node + 1, middle, end, position, digit);
+        }
+        self.nodes[node] = better(self.nodes[2 * node], self.nodes[2 * node + 1]);
+    }
+
+    /// Best battery in `from..to`, which must be non-empty.
+    fn query(&self, from: usize, to: usize) -> (u64, usize) {
+        self.query_node(1, 0, self.len, from, to).unwrap()
+    }
+
+    fn query_node(
+        &self,
+        node: usize,
+        start: usize,
+        end: usize,
+        from: usize,
+        to: usize,
+    ) -> Option<(u64, usize)> {
+        if to <= start || end <= from {
+            return None;
+        }
+        if from <= start && end <= to {
+            return Some(self.nodes[node]);
+        }
+        let middle = (start + end) / 2;
+        let left = self.query_node(2 * node, start, middle, from, to);
+        let right = self.query_node(2 * node + 1, middle, end, from, to);
+        match (left, right) {
+            (Some(left), Some(right)) => Some(better(left, right)),
+            (left, right) => left.or(right),
+        }
+    }
+
+    /// Largest `k`-digit joltage, taking each digit as the leftmost maximum of the window that
+    /// still leaves room for the rest, in O(k log n).
+    fn select(&self, k: usize) -> Option<Selection> {
+        if k > self.len {
+            return None;
+        }
+        let mut indices = Vec::with_capacity(k);
+        let mut digits = Vec::with_capacity(k);
+        let mut start = 0;
+        for i in 0..k {
+            let (digit, index) = self.query(start, self.len - k + i + 1);
+            indices.push(index);
+            digits.push(digit);
+            start = index + 1;
+        }
+        Some(Selection { indices, joltage: Joltage::from_digits(&digits) })
+    }
+}
+
+fn report(bank: &Bank, tree: &SegmentTree, k: usize) {
+    match tree.select(k) {
+        Some(selection) => println!(
+            "{} joltage: {}",
+            highlight(bank, &selection, Format::Brackets),
+            selection.joltage
+        ),
+        None => println!("bank has fewer than {} batteries", k),
+    }
+}
+
+/// Reads a bank from the first line of stdin, then applies one command per line:
+/// `set <position> <digit>`, `k <count>` or `show`.
+pub fn run(mut k: usize) {
+    let mut stdin = std::io::stdin().lock();
+    let mut bank = match BankReader::new(&mut stdin).next() {
+        Some(Ok(bank)) => bank,
+        Some(Err(e)) => {
+            eprintln!("invalid bank {}", e);
+            std::process::exit(1);
+        }
+        None => return,
+    };
+    let mut tree = SegmentTree::new(&bank.batteries);
+    report(&bank, &tree, k);
+
+    for line in stdin.lines() {
+        let line = line.unwrap();
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        match words.as_slice() {
+            [] => continue,
+            ["set", position, digit] => {
+                let position = position.parse::<usize>().ok().filter(|&p| p < bank.batteries.len());
+                let digit = digit.parse::<u64>().ok().filter(|&d| d <= 9);
+                let (Some(position), Some(digit)) = (position, digit) else {
+                    eprintln!("usage: set <position below {}> <digit>", bank.batteries.len());
+                    continue;
+                };
+                bank.batteries[position] = digit;
+                tree.update(position, digit);
+            }
+            ["k", count] => match count.parse::<usize>() {
+                Ok(count) => k = count,
+                Err(_) => {
+                    eprintln!("usage: k <count>");
+                    continue;
+                }
+            },
+            ["show"] => {}
+            _ => {
+                eprintln!("unknown command: {}", line);
+                continue;
+            }
+        }
+        report(&bank, &tree, k);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::constraints::{select_dp, Constraints, Objective};
+use crate::joltage::Joltage;
+use crate::top::TopValues;
+
+#[derive(Debug)]
+pub struct Bank {
+    pub batteries: Vec<u64>,
+}
+
+impl Bank {
+    /// Distinct values of `k` batteries chosen in order, largest first, computed lazily.
+    pub fn top_values(&self, k: usize) -> TopValues<'_> {
+        TopValues::new(&self.batteries, k)
+    }
+}
+
+#[derive(Debug)]
+pub struct Selection {
+    pub indices: Vec<usize>,
+    pub joltage: Joltage,
+}
+
+/// Best value formed by choosing `k` batteries in order under `constraints`, or `None` if no
+/// choice satisfies them. With the default constraints this is the largest `k`-digit joltage.
+pub fn select(bank: &Bank, k: usize, constraints: &Constraints) -> Option<Selection> {
+    let batteries = &bank.batteries;
+    let indices = if constraints.allows_greedy() {
+        let allowed = (0..batteries.len()).filter(|i| !constraints.forbidden.contains(i));
+        select_greedy(batteries, allowed.collect(), k, constraints.objective)?
+    } else {
+        select_dp(batteries, k, constraints)?
+    };
+    let digits = indices.iter().map(|&index| batteries[index]).collect::<Vec<u64>>();
+    Some(Selection { indices, joltage: Joltage::from_digits(&digits) })
+}
+
+/// Keeps a stack of chosen indices and pops any battery that a better later one can replace
+/// while enough batteries remain, so every battery is pushed and popped at most once.
+fn select_greedy(
+    batteries: &[u64],
+    allowed: Vec<usize>,
+    k: usize,
+    objective: Objective,
+) -> Option<Vec<usize>> {
+    if k > allowed.len() {
+        return None;
+    }
+    let mut droppable = allowed.len() - k;
+    let mut indices: Vec<usize> = Vec::with_capacity(allowed.len());
+    for index in allowed {
+        let battery = batteries[index];
+        let replaces = |last: &usize| objective.prefers(battery, batteries[*last]);
+        while droppable > 0 && indices.last().is_some_and(replaces) {
+            indices.pop();
+            droppable -= 1;
+        }
+        indices.push(index);
+    }
+    indices.truncate(k);
+    Some(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::BankReader;
+    use crate::testing::{bank, combinations, digits_at};
+
+    fn total(input: &str, k: usize) -> String {
+        let banks = BankReader::new(input.as_bytes()).map(|bank| bank.unwrap());
+        let joltages = banks.map(|bank| select(&bank, k, &Constraints::default()).unwrap().joltage);
+        joltages.fold(Joltage::Small(0), |total, joltage| total + joltage).to_string()
+    }
+
+    #[test]
+    fn reproduces_puzzle_answers() {
+        assert_eq!(total(include_str!("../test_input.txt"), 2), "357");
+        assert_eq!(total(include_str!("../input.txt"), 2), "17144");
+        assert_eq!(total(include_str!("../test_input.txt"), 12), "3121910778619");
+        assert_eq!(total(include_str!("../input.txt"), 12), "170371185255900");
+    }
+
+    #[test]
+    fn greedy_matches_brute_force() {
+        let mut rng = fastrand::Rng::with_seed(28);
+        for _ in 0..2000 {
+            let digits = rng.u64(1..=10);
+            let batteries = bank(&mut rng, 10, digits);
+            let bank = Bank { batteries: batteries.clone() };
+            for k in 0..=batteries.len() + 1 {
+                // Equal-length values compare like their digit lists; the first maximal
+                // choice in lexicographic order is the one with the earliest indices.
+                let best = combinations(batteries.len(), k)
+                    .into_iter()
+                    .reduce(|best, choice| {
+                        if digits_at(&batteries, &choice) > digits_at(&batteries, &best) { choice } else { best }
+                    });
+                let selection = select(&bank, k, &Constraints::default());
+                assert_eq!(selection.as_ref().map(|s| &s.indices), best.as_ref(), "{:?}, k = {}", batteries, k);
+                if let (Some(selection), Some(best)) = (selection, best) {
+                    assert_eq!(selection.joltage, Joltage::from_digits(&digits_at(&batteries, &best)));
+                }
+            }
+        }
+    }
+}
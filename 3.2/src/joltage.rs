@@ -0,0 +1,74 @@
+use std::ops::Add;
+
+// Any 38-digit decimal fits in a u128; longer values are kept as digits.
+const MAX_SMALL_DIGITS: usize = 38;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Joltage {
+    Small(u128),
+    /// Decimal digits, most significant first, without leading zeros.
+    Big(Vec<u8>),
+}
+
+impl Joltage {
+    pub fn from_digits(digits: &[u64]) -> Joltage {
+        let first_nonzero = digits.iter().position(|&d| d != 0).unwrap_or(digits.len());
+        let digits = &digits[first_nonzero..];
+        if digits.len() <= MAX_SMALL_DIGITS {
+            return Joltage::Small(digits.iter().fold(0, |total, &d| total * 10 + d as u128));
+        }
+        Joltage::Big(digits.iter().map(|&d| d as u8).collect())
+    }
+
+    fn to_digits(&self) -> Vec<u8> {
+        match self {
+            Joltage::Small(value) => value.to_string().bytes().map(|b| b - b'0').collect(),
+            Joltage::Big(digits) => digits.clone(),
+        }
+    }
+}
+
+fn add_digits(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    let mut a = a.iter().rev();
+    let mut b = b.iter().rev();
+    loop {
+        let (x, y) = (a.next(), b.next());
+        if x.is_none() && y.is_none() {
+            break;
+        }
+        let digit = x.unwrap_or(&0) + y.unwrap_or(&0) + carry;
+        sum.push(digit % 10);
+        carry = digit / 10;
+    }
+    if carry > 0 {
+        sum.push(carry);
+    }
+    sum.reverse();
+    sum
+}
+
+impl Add for Joltage {
+    type Output = Joltage;
+
+    fn add(self, other: Joltage) -> Joltage {
+        if let (Joltage::Small(a), Joltage::Small(b)) = (&self, &other) {
+            if let Some(sum) = a.checked_add(*b) {
+                return Joltage::Small(sum);
+            }
+        }
+        Joltage::Big(add_digits(&self.to_digits(), &other.to_digits()))
+    }
+}
+
+impl std::fmt::Display for Joltage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Joltage::Small(value) => write!(f, "{}", value),
+            Joltage::Big(digits) => {
+                write!(f, "{}", digits.iter().map(|d| (b'0' + d) as char).collect::<String>())
+            }
+        }
+    }
+}
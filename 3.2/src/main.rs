@@ -1,56 +1,106 @@
+mod bank;
+mod constraints;
+mod joltage;
+mod parse;
+mod render;
+#[cfg(test)]
+mod testing;
+mod top;
+mod what_if;
+
+use bank::{select, Bank};
+use constraints::{Constraints, Objective};
+use joltage::Joltage;
+use parse::BankReader;
+use render::{highlight, to_json, Format};
+
+const DEFAULT_BATTERY_COUNT: usize = 12;
+
 #[derive(Debug)]
-struct Bank {
-    batteries: Vec<u64>,
+struct Options {
+    k: usize,
+    format: Format,
+    constraints: Constraints,
+    top: Option<usize>,
+    what_if: bool,
 }
 
-impl From<String> for Bank {
-    fn from(string: String) -> Self {
-        let chars = string.chars().collect::<Vec<char>>();
-        let batteries = chars.iter().map(|c| c.to_string().parse::<u64>().unwrap()).collect();
-        Bank { batteries }
+fn parse_args() -> Options {
+    let mut options = Options {
+        k: DEFAULT_BATTERY_COUNT,
+        format: Format::Plain,
+        constraints: Constraints::default(),
+        top: None,
+        what_if: false,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "-k" => options.k = value().parse::<usize>().unwrap(),
+            "--format" => options.format = value().parse::<Format>().unwrap(),
+            "--what-if" => options.what_if = true,
+            "--top" => options.top = Some(value().parse::<usize>().unwrap()),
+            "--minimize" => options.constraints.objective = Objective::Minimize,
+            "--nonzero-first" => options.constraints.nonzero_first = true,
+            "--max-gap" => options.constraints.max_gap = Some(value().parse::<usize>().unwrap()),
+            "--forbid" => {
+                for position in value().split(',') {
+                    options.constraints.forbidden.insert(position.parse::<usize>().unwrap());
+                }
+            }
+            _ => panic!("unknown argument: {}", arg),
+        }
     }
+    options
 }
 
 fn main() {
-    let lines = get_lines();
-    let mut total = 0u64;
-    for line in lines {
-        let bank = Bank::from(line);
-        let length = bank.batteries.len();
-        let mut joltages = Vec::new();
-        let mut index = 0;
-        for i in 0..12 {
-            let end = length - (12 - i) + 1;
-            let (value, value_index) = find_largest_value_and_index_in_range(&bank.batteries, index, end);
-            joltages.push(value);
-            index = value_index + 1;
-        }
-        println!("bank: {:?}, joltages: {:?}", bank, joltages);
-        let mut intermediate_total = 0u64;
-        for joltage in joltages {
-            intermediate_total = intermediate_total * 10u64 + joltage;
+    let options = parse_args();
+    if options.what_if {
+        what_if::run(options.k);
+        return;
+    }
+    if let Some(count) = options.top {
+        for bank in get_banks() {
+            println!("bank: {:?}", bank);
+            for (joltage, selections) in bank.top_values(options.k).take(count) {
+                println!("  joltage: {}, selections: {}", joltage, selections);
+            }
         }
-        total += intermediate_total;
-        println!("intermediate_total: {}", intermediate_total);
+        return;
     }
-    println!("total: {}", total);
-}
-
-fn find_largest_value_and_index_in_range(values: &[u64], start: usize, end: usize) -> (u64, usize) {
-    let mut largest_value = 0;
-    let mut index = 0;
-    println!("start: {}, end: {}", start, end);
-    for i in start..end {
-        if values[i] > largest_value {
-            largest_value = values[i];
-            index = i;
+    let mut total = Joltage::Small(0);
+    let mut json = Vec::new();
+    for bank in get_banks() {
+        let selection = select(&bank, options.k, &options.constraints).unwrap_or_else(|| {
+            panic!("bank {:?} has no choice of {} batteries meeting the constraints", bank, options.k)
+        });
+        match options.format {
+            Format::Plain => println!(
+                "bank: {:?}, indices: {:?}, joltage: {}",
+                bank, selection.indices, selection.joltage
+            ),
+            Format::Ansi | Format::Brackets => {
+                println!("{} {}", highlight(&bank, &selection, options.format), selection.joltage)
+            }
+            Format::Json => json.push(to_json(&bank, &selection)),
         }
+        total = total + selection.joltage;
+    }
+    if options.format == Format::Json {
+        println!("{{\"banks\": [\n  {}\n], \"total\": {}}}", json.join(",\n  "), total);
+    } else {
+        println!("total: {}", total);
     }
-    (largest_value, index)
 }
 
-fn get_lines() -> Vec<String> {
+fn get_banks() -> impl Iterator<Item = Bank> {
     let stdin = std::io::stdin();
-    let lines = stdin.lines().map(|line| line.unwrap()).collect();
-    lines
-}
\ No newline at end of file
+    BankReader::new(stdin.lock()).map(|result| {
+        result.unwrap_or_else(|e| {
+            eprintln!("invalid bank {}", e);
+            std::process::exit(1);
+        })
+    })
+}
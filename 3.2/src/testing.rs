@@ -0,0 +1,30 @@
+//! Helpers shared by the unit tests.
+
+/// Up to `max_len` batteries with digits below `digits`; few digits make ties common.
+pub fn bank(rng: &mut fastrand::Rng, max_len: u64, digits: u64) -> Vec<u64> {
+    let len = rng.u64(..=max_len);
+    (0..len).map(|_| rng.u64(..digits)).collect()
+}
+
+/// Every increasing choice of `k` positions out of `0..n`, in lexicographic order.
+pub fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    if k > n {
+        return Vec::new();
+    }
+    let mut all = Vec::new();
+    for first in 0..=n - k {
+        for rest in combinations(n - first - 1, k - 1) {
+            let mut choice = vec![first];
+            choice.extend(rest.iter().map(|i| i + first + 1));
+            all.push(choice);
+        }
+    }
+    all
+}
+
+pub fn digits_at(batteries: &[u64], indices: &[usize]) -> Vec<u64> {
+    indices.iter().map(|&i| batteries[i]).collect()
+}
@@ -0,0 +1,121 @@
+use crate::joltage::Joltage;
+
+#[derive(Debug)]
+struct Frame {
+    /// First position the next digit may be taken from.
+    start: usize,
+    /// Next digit to try at this depth, counting down.
+    next_digit: Option<u64>,
+}
+
+/// Distinct `k`-digit values of a bank in descending order, each with the number of index
+/// selections that spell it. Values are walked as a depth-first search over digits from 9
+/// down to 0, always taking the leftmost occurrence of a digit, which keeps the most room for
+/// the rest of the value; branches without enough batteries left are never entered, so each
+/// value costs O(10·k) to find plus O(n·k) to count.
+#[derive(Debug)]
+pub struct TopValues<'a> {
+    batteries: &'a [u64],
+    k: usize,
+    /// next[i][d] is the first position at or after `i` holding digit `d`.
+    next: Vec<[usize; 10]>,
+    stack: Vec<Frame>,
+    prefix: Vec<u64>,
+}
+
+impl<'a> TopValues<'a> {
+    pub fn new(batteries: &'a [u64], k: usize) -> Self {
+        let n = batteries.len();
+        let mut next = vec![[n; 10]; n + 1];
+        for i in (0..n).rev() {
+            next[i] = next[i + 1];
+            next[i][batteries[i] as usize] = i;
+        }
+        let stack = if k <= n { vec![Frame { start: 0, next_digit: Some(9) }] } else { Vec::new() };
+        TopValues { batteries, k, next, stack, prefix: Vec::with_capacity(k) }
+    }
+
+    /// Number of index selections spelling `digits`, saturating at `u128::MAX`.
+    fn count_selections(&self, digits: &[u64]) -> u128 {
+        let mut ways = vec![0u128; digits.len() + 1];
+        ways[0] = 1;
+        for &battery in self.batteries {
+            for j in (1..=digits.len()).rev() {
+                if digits[j - 1] == battery {
+                    ways[j] = ways[j].saturating_add(ways[j - 1]);
+                }
+            }
+        }
+        ways[digits.len()]
+    }
+}
+
+impl Iterator for TopValues<'_> {
+    type Item = (Joltage, u128);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.batteries.len();
+        loop {
+            if self.stack.is_empty() {
+                return None;
+            }
+            if self.prefix.len() == self.k {
+                let value = (Joltage::from_digits(&self.prefix), self.count_selections(&self.prefix));
+                self.stack.pop();
+                self.prefix.pop();
+                return Some(value);
+            }
+            let remaining = self.k - self.prefix.len() - 1;
+            let frame = self.stack.last_mut().unwrap();
+            let mut found = None;
+            while let Some(digit) = frame.next_digit {
+                frame.next_digit = digit.checked_sub(1);
+                let position = self.next[frame.start][digit as usize];
+                if position < n && n - position > remaining {
+                    found = Some((digit, position));
+                    break;
+                }
+            }
+            match found {
+                Some((digit, position)) => {
+                    self.prefix.push(digit);
+                    self.stack.push(Frame { start: position + 1, next_digit: Some(9) });
+                }
+                None => {
+                    self.stack.pop();
+                    self.prefix.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::testing::{bank, combinations, digits_at};
+
+    #[test]
+    fn yields_every_value_with_its_count() {
+        let mut rng = fastrand::Rng::with_seed(32);
+        for _ in 0..2000 {
+            let digits = rng.u64(1..=10);
+            let batteries = bank(&mut rng, 10, digits);
+            for k in 0..=batteries.len() + 1 {
+                let mut counts = BTreeMap::new();
+                for choice in combinations(batteries.len(), k) {
+                    *counts.entry(digits_at(&batteries, &choice)).or_insert(0u128) += 1;
+                }
+                let expected = counts
+                    .into_iter()
+                    .rev()
+                    .map(|(digits, count)| (Joltage::from_digits(&digits), count))
+                    .collect::<Vec<(Joltage, u128)>>();
+                let values = TopValues::new(&batteries, k).collect::<Vec<(Joltage, u128)>>();
+                assert_eq!(values, expected, "{:?}, k = {}", batteries, k);
+            }
+        }
+    }
+}
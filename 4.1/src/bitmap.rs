@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::rules::Rules;
+use crate::wall::{Product, Wall};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Text,
+    Pbm,
+    Png,
+}
+
+impl Format {
+    fn of(path: &Path) -> Format {
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("pbm") => Format::Pbm,
+            Some("png") => Format::Png,
+            _ => Format::Text,
+        }
+    }
+}
+
+/// Loads a wall from the `@`/`.` text format, or from a PBM or PNG image in which black
+/// pixels are paper rolls, depending on the file extension.
+pub fn load_wall(path: &Path) -> anyhow::Result<Wall> {
+    match Format::of(path) {
+        Format::Text => Wall::from_str(&std::fs::read_to_string(path)?),
+        Format::Pbm => read_pbm(&std::fs::read(path)?),
+        Format::Png => read_png(path),
+    }
+}
+
+/// Writes an RGB PNG with one pixel per cell: empty cells are white, and rolls shade from
+/// pale yellow with no paper neighbours to dark red with every neighbour holding paper.
+pub fn write_heatmap(wall: &Wall, rules: &Rules, path: &Path) -> anyhow::Result<()> {
+    let max_count = rules.neighborhood.offsets(0).len().max(1) as f64;
+    let mut pixels = Vec::with_capacity(wall.get_width() * wall.get_height() * 3);
+    for y in 0..wall.get_height() {
+        let row_counts = wall.count_row_neighbors(y, rules);
+        for (x, &count) in row_counts.iter().enumerate() {
+            if wall.get(x, y) != Some(Product::Paper) {
+                pixels.extend([255, 255, 255]);
+                continue;
+            }
+            let heat = (count as f64 / max_count).min(1.0);
+            let shade = |cold: f64, hot: f64| (cold + (hot - cold) * heat).round() as u8;
+            pixels.extend([shade(255.0, 128.0), shade(240.0, 0.0), shade(160.0, 0.0)]);
+        }
+    }
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, wall.get_width() as u32, wall.get_height() as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+    Ok(())
+}
+
+fn from_pixels(width: usize, height: usize, is_paper: impl Fn(usize, usize) -> bool) -> Wall {
+    let product = |x, y| if is_paper(x, y) { Product::Paper } else { Product::Nothing };
+    let products = (0..height).map(|y| (0..width).map(|x| product(x, y)).collect());
+    Wall::from(products.collect::<Vec<Vec<Product>>>())
+}
+
+/// Splits off the next whitespace-separated header token, skipping `#` comments.
+fn next_token<'a>(data: &'a [u8], position: &mut usize) -> anyhow::Result<&'a [u8]> {
+    loop {
+        while data.get(*position).is_some_and(|b| b.is_ascii_whitespace()) {
+            *position += 1;
+        }
+        if data.get(*position) != Some(&b'#') {
+            break;
+        }
+        while data.get(*position).is_some_and(|&b| b != b'\n') {
+            *position += 1;
+        }
+    }
+    let start = *position;
+    while data.get(*position).is_some_and(|b| !b.is_ascii_whitespace()) {
+        *position += 1;
+    }
+    if start == *position {
+        return Err(anyhow!("Truncated PBM header"));
+    }
+    Ok(&data[start..*position])
+}
+
+fn read_pbm(data: &[u8]) -> anyhow::Result<Wall> {
+    let mut position = 0;
+    let magic = next_token(data, &mut position)?;
+    let mut dimension = || -> anyhow::Result<usize> {
+        let token = std::str::from_utf8(next_token(data, &mut position)?)?;
+        token.parse::<usize>().map_err(|_| anyhow!("Invalid PBM dimension: {}", token))
+    };
+    let (width, height) = (dimension()?, dimension()?);
+    match magic {
+        b"P1" => {
+            let bits = data[position..]
+                .iter()
+                .filter(|b| !b.is_ascii_whitespace())
+                .map(|&b| match b {
+                    b'0' => Ok(false),
+                    b'1' => Ok(true),
+                    _ => Err(anyhow!("Invalid PBM pixel: {}", b as char)),
+                })
+                .collect::<anyhow::Result<Vec<bool>>>()?;
+            if bits.len() < width * height {
+                return Err(anyhow!("PBM has {} pixels, expected {}", bits.len(), width * height));
+            }
+            Ok(from_pixels(width, height, |x, y| bits[y * width + x]))
+        }
+        b"P4" => {
+            // Exactly one whitespace byte separates the header from the raster.
+            let raster = data.get(position + 1..).unwrap_or_default();
+            let row_bytes = width.div_ceil(8);
+            if raster.len() < row_bytes * height {
+                let expected = row_bytes * height;
+                return Err(anyhow!("PBM raster is {} bytes, expected {}", raster.len(), expected));
+            }
+            let is_paper = |x: usize, y: usize| raster[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0;
+            Ok(from_pixels(width, height, is_paper))
+        }
+        _ => Err(anyhow!("Not a PBM file: {}", String::from_utf8_lossy(magic))),
+    }
+}
+
+fn read_png(path: &Path) -> anyhow::Result<Wall> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let samples = info.color_type.samples();
+    let luminance = |r: u8, g: u8, b: u8| (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+    let is_paper = |x: usize, y: usize| {
+        let pixel = &buffer[y * info.line_size + x * samples..][..samples];
+        let (luminance, alpha) = match *pixel {
+            [gray] => (gray as u32, 255),
+            [gray, alpha] => (gray as u32, alpha),
+            [r, g, b] => (luminance(r, g, b), 255),
+            [r, g, b, alpha, ..] => (luminance(r, g, b), alpha),
+            [] => (255, 255),
+        };
+        // Transparent pixels are background whatever their colour.
+        alpha >= 128 && luminance < 128
+    };
+    Ok(from_pixels(info.width as usize, info.height as usize, is_paper))
+}
@@ -1,100 +1,78 @@
+mod bitmap;
+mod packed;
+mod rules;
+mod wall;
+
 use std::io::Read;
+use std::path::PathBuf;
 use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-enum Product {
-    Paper,
-    Nothing,
-}
-
-impl FromStr for Product {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "@" => Ok(Product::Paper),
-            "." => Ok(Product::Nothing),
-            _ => Err(anyhow::anyhow!("Invalid product: {}", s)),
-        }
-    }
-}
+use rules::{Neighborhood, Rules};
+use wall::{Product, Wall};
 
-#[derive(Debug)]
-struct Wall {
-    products: Vec<Vec<Product>>,
+#[derive(Debug, Default)]
+struct Options {
+    rules: Rules,
+    input: Option<PathBuf>,
+    heatmap: Option<PathBuf>,
 }
 
-impl Wall {
-    fn get(&self, x: usize, y: usize) -> Option<Product> {
-        self.products.get(y)?.get(x).copied()
-    }
-    
-    fn get_width(&self) -> usize {
-        self.products.first().unwrap().len()
-    }
-    
-    fn get_height(&self) -> usize {
-        self.products.len()
-    }
-
-    fn get_neighbors(&self, x: usize, y: usize) -> Vec<Option<Product>> {
-        let mut neighbors = Vec::new();
-        for i in vec![-1i32, 0i32, 1i32] {
-            for j in vec![-1i32, 0i32, 1i32] {
-                if i == 0i32 && j == 0i32 {
-                    continue;
-                }
-                let neighbor = self.get((x as i32 + i) as usize, (y as i32 + j) as usize);
-                neighbors.push(neighbor);
-            }
+fn parse_args() -> Options {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--neighborhood" => options.rules.neighborhood = Neighborhood::from_str(&value()).unwrap(),
+            "--torus" => options.rules.toroidal = true,
+            "--threshold" => options.rules.threshold = value().parse::<usize>().unwrap(),
+            "--input" => options.input = Some(value().into()),
+            "--heatmap" => options.heatmap = Some(value().into()),
+            _ => panic!("unknown argument: {}", arg),
         }
-        neighbors
-    }
-
-    fn count_neighbors(&self, x: usize, y: usize) -> usize {
-        let neighbors = self.get_neighbors(x, y);
-        neighbors.iter().filter(|n| n.is_some() && **n != Some(Product::Nothing)).count()
     }
+    options
 }
 
-impl FromStr for Wall {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lines = s.lines();
-        let mut products = Vec::new();
-        for line in lines {
-            let chars = line.chars().collect::<Vec<char>>();
-            let mut inner_products = Vec::new();
-            for char in chars {
-                let product = Product::from_str(&char.to_string()).unwrap();
-                inner_products.push(product);
-            }
-            products.push(inner_products);
+fn main() {
+    let options = parse_args();
+    let rules = options.rules;
+    let wall = match &options.input {
+        Some(path) => bitmap::load_wall(path).unwrap(),
+        None => {
+            let mut input = String::new();
+            let _read_bytes = std::io::stdin().read_to_string(&mut input);
+            Wall::from_str(&input).unwrap()
         }
-
-        Ok(Wall { products })
+    };
+    rules.check(wall.get_width(), wall.get_height()).unwrap();
+    // With a heatmap the per-cell counts go to the image instead of the terminal.
+    let verbose = options.heatmap.is_none();
+    if verbose {
+        println!("wall: {:?}", wall);
     }
-}
-
-fn main() {
-    let mut input = String::new();
-    let _read_bytes = std::io::stdin().read_to_string(&mut input);
-    let wall = Wall::from_str(&input).unwrap();
-    println!("wall: {:?}", wall);
 
     let mut forkable = 0;
     for y in 0..wall.get_height() {
-        for x in 0..wall.get_width() {
-            let neighbors = wall.get_neighbors(x, y);
-            let count = wall.count_neighbors(x, y);
-            let product = wall.get(x, y);
-            if count < 4 && product == Some(Product::Paper) {
+        let row_counts = wall.count_row_neighbors(y, &rules);
+        for (x, &count) in row_counts.iter().enumerate() {
+            if (count as usize) < rules.threshold && wall.get(x, y) == Some(Product::Paper) {
                 forkable += 1;
             }
-            println!("x: {}, y: {}, count: {}, forkable: {}", x, y, count, count <= 4);
-            println!("neighbors: {:?}", neighbors);
         }
     }
+    // The listing shows every cell's neighbours anyway, so it counts them cell by cell.
+    if verbose {
+        for y in 0..wall.get_height() {
+            for x in 0..wall.get_width() {
+                let count = wall.count_neighbors(x, y, &rules);
+                println!("x: {}, y: {}, count: {}, forkable: {}", x, y, count, count < rules.threshold);
+                println!("neighbors: {:?}", wall.get_neighbors(x, y, &rules));
+            }
+        }
+    }
+    if let Some(path) = &options.heatmap {
+        bitmap::write_heatmap(&wall, &rules, path).unwrap();
+    }
     println!("forkable: {}", forkable);
 }
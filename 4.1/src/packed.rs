@@ -0,0 +1,166 @@
+const WORD_BITS: usize = 64;
+// Enough bits to count up to 15 neighbours; the largest neighbourhood has 8.
+const COUNT_PLANES: usize = 4;
+
+/// A grid of bits, one per cell, packed into `u64` words row by row. Cell `x` of a row is bit
+/// `x % 64` of word `x / 64`, and padding bits past the width are always clear.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackedRows {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl PackedRows {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        PackedRows { width, height, words_per_row, words: vec![0; words_per_row * height] }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<bool> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let word = self.words[y * self.words_per_row + x / WORD_BITS];
+        Some(word >> (x % WORD_BITS) & 1 == 1)
+    }
+
+    /// Panics if `(x, y)` is outside the grid.
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        assert!(x < self.width && y < self.height, "({}, {}) is outside the wall", x, y);
+        let word = &mut self.words[y * self.words_per_row + x / WORD_BITS];
+        let mask = 1u64 << (x % WORD_BITS);
+        if value {
+            *word |= mask;
+        } else {
+            *word &= !mask;
+        }
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// Row `y` moved so that bit `x` of the result holds cell `x + dx`, for `dx` in -1..=1.
+    fn shifted_row(&self, y: usize, dx: i32, toroidal: bool) -> Vec<u64> {
+        let row = self.row(y);
+        let last = self.words_per_row - 1;
+        let next_low_bit = |i: usize| row.get(i + 1).map_or(0, |next| next << 63);
+        let previous_high_bit = |i: usize| i.checked_sub(1).map_or(0, |previous| row[previous] >> 63);
+        let mut shifted = match dx {
+            1 => (0..=last).map(|i| row[i] >> 1 | next_low_bit(i)).collect(),
+            -1 => (0..=last).map(|i| row[i] << 1 | previous_high_bit(i)).collect(),
+            _ => row.to_vec(),
+        };
+        if toroidal && dx != 0 {
+            let (to, from) = if dx == 1 { (self.width - 1, 0) } else { (0, self.width - 1) };
+            if self.get(from, y) == Some(true) {
+                shifted[to / WORD_BITS] |= 1 << (to % WORD_BITS);
+            }
+        }
+        let padding = self.words_per_row * WORD_BITS - self.width;
+        shifted[last] &= u64::MAX >> padding;
+        shifted
+    }
+
+    /// Number of set cells at `offsets` from each cell of row `y`, for the whole row at once:
+    /// every offset contributes a shifted copy of a row, and the copies are summed 64 cells
+    /// at a time with a bit-sliced ripple adder over four count planes.
+    pub fn neighbor_counts(&self, y: usize, offsets: &[(i32, i32)], toroidal: bool) -> Vec<u8> {
+        if self.width == 0 {
+            return Vec::new();
+        }
+        let mut planes = vec![vec![0u64; self.words_per_row]; COUNT_PLANES];
+        for &(dx, dy) in offsets {
+            let ny = y as i64 + dy as i64;
+            let ny = match (toroidal, ny) {
+                (true, ny) => ny.rem_euclid(self.height as i64) as usize,
+                (false, ny) if ny < 0 || ny >= self.height as i64 => continue,
+                (false, ny) => ny as usize,
+            };
+            let mut carries = self.shifted_row(ny, dx, toroidal);
+            for plane in planes.iter_mut() {
+                for (bit, carry) in plane.iter_mut().zip(carries.iter_mut()) {
+                    let sum = *bit ^ *carry;
+                    *carry &= *bit;
+                    *bit = sum;
+                }
+            }
+        }
+        (0..self.width)
+            .map(|x| {
+                let (word, bit) = (x / WORD_BITS, x % WORD_BITS);
+                let bits = planes.iter().map(|plane| (plane[word] >> bit & 1) as u8);
+                bits.enumerate().map(|(p, bit)| bit << p).sum()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PackedRows;
+    use crate::rules::{Neighborhood, Rules};
+
+    /// Neighbour count of a single cell, looked up one offset at a time.
+    fn count_cell(rows: &PackedRows, x: usize, y: usize, rules: &Rules) -> u8 {
+        let (width, height) = (rows.width() as i64, rows.height() as i64);
+        let mut count = 0;
+        for &(dx, dy) in rules.neighborhood.offsets(y) {
+            let (mut nx, mut ny) = (x as i64 + dx as i64, y as i64 + dy as i64);
+            if rules.toroidal {
+                (nx, ny) = (nx.rem_euclid(width), ny.rem_euclid(height));
+            } else if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                continue;
+            }
+            count += rows.get(nx as usize, ny as usize).unwrap() as u8;
+        }
+        count
+    }
+
+    #[test]
+    fn row_counts_match_per_cell_counts() {
+        let mut rng = fastrand::Rng::with_seed(40);
+        let neighborhoods = [Neighborhood::Moore, Neighborhood::VonNeumann, Neighborhood::Hexagonal];
+        for width in [1, 2, 3, 63, 64, 65, 127, 128, 129] {
+            for neighborhood in neighborhoods {
+                for toroidal in [false, true] {
+                    for _ in 0..20 {
+                        let mut height = rng.usize(1..=6);
+                        if toroidal {
+                            height = height.max(3);
+                        }
+                        if toroidal && neighborhood == Neighborhood::Hexagonal {
+                            height += height % 2;
+                        }
+                        let rules = Rules { neighborhood, toroidal, threshold: 4 };
+                        if rules.check(width, height).is_err() {
+                            continue;
+                        }
+                        let density = rng.usize(1..=9);
+                        let mut rows = PackedRows::new(width, height);
+                        for y in 0..height {
+                            for x in 0..width {
+                                rows.set(x, y, rng.usize(..10) < density);
+                            }
+                        }
+                        for y in 0..height {
+                            let expected = (0..width).map(|x| count_cell(&rows, x, y, &rules)).collect::<Vec<u8>>();
+                            let counts = rows.neighbor_counts(y, neighborhood.offsets(y), toroidal);
+                            assert_eq!(counts, expected, "{:?} toroidal={} {}x{} row {}", neighborhood, toroidal, width, height, y);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
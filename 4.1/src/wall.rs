@@ -0,0 +1,127 @@
+use std::str::FromStr;
+
+use crate::packed::PackedRows;
+use crate::rules::Rules;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Product {
+    Paper,
+    Nothing,
+}
+
+impl FromStr for Product {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "@" => Ok(Product::Paper),
+            "." => Ok(Product::Nothing),
+            _ => Err(anyhow::anyhow!("Invalid product: {}", s)),
+        }
+    }
+}
+
+/// Stored one bit per cell, set for paper, so very large walls stay compact and whole rows
+/// of neighbour counts can be computed word by word.
+pub struct Wall {
+    paper: PackedRows,
+}
+
+impl Wall {
+    pub fn get(&self, x: usize, y: usize) -> Option<Product> {
+        let paper = self.paper.get(x, y)?;
+        Some(if paper { Product::Paper } else { Product::Nothing })
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.paper.width()
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.paper.height()
+    }
+
+    /// Position `offset` away from `(x, y)`, wrapping around the edges of a toroidal wall, or
+    /// `None` if it falls off the wall.
+    fn offset(
+        &self,
+        x: usize,
+        y: usize,
+        (dx, dy): (i32, i32),
+        rules: &Rules,
+    ) -> Option<(usize, usize)> {
+        let (width, height) = (self.get_width() as i64, self.get_height() as i64);
+        let (nx, ny) = (x as i64 + dx as i64, y as i64 + dy as i64);
+        if rules.toroidal {
+            return Some((nx.rem_euclid(width) as usize, ny.rem_euclid(height) as usize));
+        }
+        if nx < 0 || ny < 0 || nx >= width || ny >= height {
+            return None;
+        }
+        Some((nx as usize, ny as usize))
+    }
+
+    pub fn get_neighbors(&self, x: usize, y: usize, rules: &Rules) -> Vec<Option<Product>> {
+        let mut neighbors = Vec::new();
+        for &offset in rules.neighborhood.offsets(y) {
+            let neighbor = self.offset(x, y, offset, rules).and_then(|(nx, ny)| self.get(nx, ny));
+            neighbors.push(neighbor);
+        }
+        neighbors
+    }
+
+    pub fn count_neighbors(&self, x: usize, y: usize, rules: &Rules) -> usize {
+        let neighbors = self.get_neighbors(x, y, rules);
+        neighbors.iter().filter(|n| n.is_some() && **n != Some(Product::Nothing)).count()
+    }
+
+    /// `count_neighbors` for every cell of row `y` at once.
+    pub fn count_row_neighbors(&self, y: usize, rules: &Rules) -> Vec<u8> {
+        self.paper.neighbor_counts(y, rules.neighborhood.offsets(y), rules.toroidal)
+    }
+}
+
+impl std::fmt::Debug for Wall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = (0..self.get_height())
+            .map(|y| {
+                let cell = |x| if self.get(x, y) == Some(Product::Paper) { '@' } else { '.' };
+                (0..self.get_width()).map(cell).collect::<String>()
+            })
+            .collect::<Vec<String>>();
+        f.debug_struct("Wall").field("rows", &rows).finish()
+    }
+}
+
+impl From<Vec<Vec<Product>>> for Wall {
+    fn from(products: Vec<Vec<Product>>) -> Self {
+        let width = products.iter().map(Vec::len).max().unwrap_or(0);
+        let mut paper = PackedRows::new(width, products.len());
+        for (y, row) in products.iter().enumerate() {
+            for (x, product) in row.iter().enumerate() {
+                paper.set(x, y, *product == Product::Paper);
+            }
+        }
+        Wall { paper }
+    }
+}
+
+impl FromStr for Wall {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines();
+        let mut products = Vec::new();
+        for line in lines {
+            let chars = line.chars().collect::<Vec<char>>();
+            let mut inner_products = Vec::new();
+            for char in chars {
+                let product = Product::from_str(&char.to_string()).unwrap();
+                inner_products.push(product);
+            }
+            products.push(inner_products);
+        }
+
+        Ok(Wall::from(products))
+    }
+}
@@ -0,0 +1,388 @@
+use anyhow::anyhow;
+
+use crate::rules::Rules;
+use crate::wall::{Product, Wall};
+
+// Walls with at most this many cells get an exact reinforcement search; larger ones get
+// the greedy answer.
+const EXACT_CELL_LIMIT: usize = 100;
+
+/// A roll that would survive peeling, and how many rolls fall if it is taken out first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cascade {
+    pub position: (usize, usize),
+    /// Rolls removed, counting this one, that peeling would otherwise leave standing.
+    pub size: usize,
+}
+
+/// Empty cells to fill with rolls so that no roll on the wall is accessible.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reinforcement {
+    pub added: Vec<(usize, usize)>,
+    /// Whether `added` is known to be as small as possible.
+    pub exact: bool,
+}
+
+/// Paper and neighbour counts of every cell, indexed `y * width + x`, that rolls can be
+/// added to and taken from.
+struct Grid<'a> {
+    wall: &'a Wall,
+    rules: &'a Rules,
+    paper: Vec<bool>,
+    counts: Vec<usize>,
+}
+
+impl<'a> Grid<'a> {
+    fn new(wall: &'a Wall, rules: &'a Rules, paper: Vec<bool>) -> Self {
+        let mut grid = Grid { wall, rules, counts: vec![0; paper.len()], paper };
+        for index in 0..grid.paper.len() {
+            grid.counts[index] = grid.neighbors(index).filter(|&n| grid.paper[n]).count();
+        }
+        grid
+    }
+
+    fn of_wall(wall: &'a Wall, rules: &'a Rules) -> Self {
+        let width = wall.get_width();
+        let positions = (0..width * wall.get_height()).map(|index| (index % width, index / width));
+        let paper = positions.clone().map(|(x, y)| wall.get(x, y) == Some(Product::Paper)).collect();
+        let counts = positions.map(|(x, y)| wall.count_neighbors(x, y, rules)).collect();
+        Grid { wall, rules, paper, counts }
+    }
+
+    fn position(&self, index: usize) -> (usize, usize) {
+        (index % self.wall.get_width(), index / self.wall.get_width())
+    }
+
+    fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let (x, y) = self.position(index);
+        let width = self.wall.get_width();
+        self.wall.neighbor_positions(x, y, self.rules).map(move |(nx, ny)| ny * width + nx)
+    }
+
+    fn deficit(&self, index: usize) -> usize {
+        self.rules.threshold.saturating_sub(self.counts[index])
+    }
+
+    fn set(&mut self, index: usize, paper: bool) {
+        self.paper[index] = paper;
+        let neighbors = self.neighbors(index).collect::<Vec<usize>>();
+        for neighbor in neighbors {
+            if paper {
+                self.counts[neighbor] += 1;
+            } else {
+                self.counts[neighbor] -= 1;
+            }
+        }
+    }
+
+    /// Takes out `index` and every roll that becomes accessible as a result, leaving rolls
+    /// for which `pinned` holds in place. Returns the removed cells, or the first pinned
+    /// roll that became accessible.
+    fn collapse(&mut self, index: usize, pinned: impl Fn(usize) -> bool) -> Result<Vec<usize>, usize> {
+        let mut removed = vec![index];
+        let mut pending = vec![index];
+        self.paper[index] = false;
+        while let Some(cell) = pending.pop() {
+            let neighbors = self.neighbors(cell).collect::<Vec<usize>>();
+            for neighbor in neighbors {
+                self.counts[neighbor] -= 1;
+                if !self.paper[neighbor] || self.counts[neighbor] >= self.rules.threshold {
+                    continue;
+                }
+                if pinned(neighbor) {
+                    return Err(neighbor);
+                }
+                self.paper[neighbor] = false;
+                removed.push(neighbor);
+                pending.push(neighbor);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Puts back the rolls taken out by `collapse`.
+    fn restore(&mut self, removed: &[usize]) {
+        for &index in removed {
+            self.paper[index] = true;
+        }
+        for &index in removed {
+            let neighbors = self.neighbors(index).collect::<Vec<usize>>();
+            for neighbor in neighbors {
+                self.counts[neighbor] += 1;
+            }
+        }
+    }
+}
+
+impl Wall {
+    /// Rolls that survive peeling, ordered by how many rolls fall when each is taken out
+    /// first, largest cascade first. Rolls that peeling removes anyway trigger nothing new,
+    /// so they are left out.
+    pub fn rank_cascades(&self, rules: &Rules) -> Vec<Cascade> {
+        let mut core = self.clone();
+        core.peel(rules);
+        let mut grid = Grid::of_wall(&core, rules);
+        let mut cascades = Vec::new();
+        for index in 0..grid.paper.len() {
+            if !grid.paper[index] {
+                continue;
+            }
+            let removed = grid.collapse(index, |_| false).expect("nothing is pinned");
+            grid.restore(&removed);
+            cascades.push(Cascade { position: grid.position(index), size: removed.len() });
+        }
+        cascades.sort_by_key(|cascade| (std::cmp::Reverse(cascade.size), cascade.position.1, cascade.position.0));
+        cascades
+    }
+
+    /// The fewest rolls to add so that no roll is accessible any more, searched exactly on
+    /// small walls and chosen greedily on large ones. Fails if some roll can never gather
+    /// enough neighbours, such as a roll in the corner of a flat wall.
+    pub fn reinforce(&self, rules: &Rules) -> anyhow::Result<Reinforcement> {
+        let region = self.reinforceable_region(rules)?;
+        let mut grid = Grid::of_wall(self, rules);
+        let greedy = grid.greedy_reinforcement(&region);
+        let cells = self.get_width() * self.get_height();
+        if cells > EXACT_CELL_LIMIT {
+            let added = greedy.iter().map(|&index| grid.position(index)).collect();
+            return Ok(Reinforcement { added, exact: false });
+        }
+        let mut search = Search { best: greedy, added: Vec::new(), excluded: vec![false; cells] };
+        search.run(&mut grid, &region);
+        let added = search.best.iter().map(|&index| grid.position(index)).collect();
+        Ok(Reinforcement { added, exact: true })
+    }
+
+    /// Cells that may hold a roll in some reinforced wall: starting from a full wall, empty
+    /// cells that can never gather enough neighbours are dropped until the rest hold.
+    fn reinforceable_region(&self, rules: &Rules) -> anyhow::Result<Vec<bool>> {
+        let empty = Grid::of_wall(self, rules).paper.iter().map(|&paper| !paper).collect::<Vec<bool>>();
+        let mut full = Grid::new(self, rules, vec![true; empty.len()]);
+        for index in 0..empty.len() {
+            if !full.paper[index] || full.counts[index] >= rules.threshold {
+                continue;
+            }
+            if !empty[index] {
+                let (x, y) = full.position(index);
+                return Err(anyhow!("The roll at ({}, {}) can never have {} neighbours", x, y, rules.threshold));
+            }
+            if let Err(roll) = full.collapse(index, |cell| !empty[cell]) {
+                let (x, y) = full.position(roll);
+                return Err(anyhow!("The roll at ({}, {}) can never have {} neighbours", x, y, rules.threshold));
+            }
+        }
+        Ok(full.paper)
+    }
+}
+
+impl Grid<'_> {
+    /// Empty cells of `region` next to `index`, where a roll could be added.
+    fn options(&self, index: usize, region: &[bool], excluded: &[bool]) -> Vec<usize> {
+        self.neighbors(index).filter(|&n| region[n] && !self.paper[n] && !excluded[n]).collect()
+    }
+
+    /// Tops up accessible rolls one at a time, each time adding the candidate next to the
+    /// most accessible rolls. Always succeeds inside the reinforceable region, which holds
+    /// once it is full.
+    fn greedy_reinforcement(&mut self, region: &[bool]) -> Vec<usize> {
+        let mut added = Vec::new();
+        let mut pending = (0..self.paper.len()).filter(|&i| self.paper[i]).collect::<Vec<usize>>();
+        let no_exclusions = vec![false; self.paper.len()];
+        while let Some(index) = pending.pop() {
+            while self.deficit(index) > 0 {
+                let options = self.options(index, region, &no_exclusions);
+                let score = |&cell: &usize| {
+                    let helped = self.neighbors(cell).filter(|&n| self.paper[n] && self.deficit(n) > 0).count();
+                    (helped, self.counts[cell])
+                };
+                let cell = options.into_iter().max_by_key(score).expect("region cells can always be held");
+                self.set(cell, true);
+                added.push(cell);
+                pending.push(cell);
+            }
+        }
+        for &cell in &added {
+            self.set(cell, false);
+        }
+        added
+    }
+}
+
+/// Branch and bound over which empty cells get a roll, seeded with the greedy answer.
+struct Search {
+    best: Vec<usize>,
+    added: Vec<usize>,
+    excluded: Vec<bool>,
+}
+
+impl Search {
+    fn run(&mut self, grid: &mut Grid, region: &[bool]) {
+        // The accessible roll with the least room to spare, and its total shortfall.
+        let mut tightest: Option<(usize, Vec<usize>)> = None;
+        let (mut total_deficit, mut largest_deficit) = (0, 0);
+        for index in (0..grid.paper.len()).filter(|&i| grid.paper[i] && grid.deficit(i) > 0) {
+            let deficit = grid.deficit(index);
+            let options = grid.options(index, region, &self.excluded);
+            if options.len() < deficit {
+                return;
+            }
+            total_deficit += deficit;
+            largest_deficit = largest_deficit.max(deficit);
+            let slack = options.len() - deficit;
+            if tightest.as_ref().is_none_or(|(i, o)| slack < o.len() - grid.deficit(*i)) {
+                tightest = Some((index, options));
+            }
+        }
+        let Some((_, options)) = tightest else {
+            if self.added.len() < self.best.len() {
+                self.best = self.added.clone();
+            }
+            return;
+        };
+        // Each added roll helps at most one neighbourhood's worth of rolls.
+        let most_helped = grid.rules.neighborhood.offsets(0).len();
+        let lower_bound = largest_deficit.max(total_deficit.div_ceil(most_helped));
+        if self.added.len() + lower_bound >= self.best.len() {
+            return;
+        }
+        // Branch on which option is the next one added; earlier options are left out.
+        for &cell in &options {
+            grid.set(cell, true);
+            self.added.push(cell);
+            self.run(grid, region);
+            self.added.pop();
+            grid.set(cell, false);
+            self.excluded[cell] = true;
+        }
+        for &cell in &options {
+            self.excluded[cell] = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Grid;
+    use crate::experiment::random_wall;
+    use crate::rules::{Neighborhood, Rules};
+    use crate::wall::{Product, Wall};
+
+    /// Random walls of up to 4x4 under every neighbourhood, flat and toroidal, with thresholds
+    /// low enough that many of them can be reinforced.
+    fn small_cases() -> Vec<(Wall, Rules)> {
+        let mut rng = fastrand::Rng::with_seed(41);
+        let mut cases = Vec::new();
+        for neighborhood in [Neighborhood::Moore, Neighborhood::VonNeumann, Neighborhood::Hexagonal] {
+            for toroidal in [false, true] {
+                for _ in 0..60 {
+                    let (width, height) = (rng.usize(1..=4), rng.usize(1..=4));
+                    let rules = Rules { neighborhood, toroidal, threshold: rng.usize(1..=4) };
+                    if rules.check(width, height).is_ok() {
+                        cases.push((random_wall(width, height, rng.f64(), rng.u64(..)), rules));
+                    }
+                }
+            }
+        }
+        cases
+    }
+
+    fn with_rolls_added(wall: &Wall, added: &[(usize, usize)]) -> Wall {
+        let mut reinforced = wall.clone();
+        for &(x, y) in added {
+            assert_eq!(wall.get(x, y), Some(Product::Nothing), "({}, {}) already holds a roll", x, y);
+            reinforced.set(x, y, Product::Paper);
+        }
+        reinforced
+    }
+
+    /// The fewest empty cells to fill so that no roll is accessible, found by trying every
+    /// subset of the empty cells. Cells are bits of a mask, indexed `y * width + x`.
+    fn fewest_rolls_to_add(wall: &Wall, rules: &Rules) -> Option<usize> {
+        let width = wall.get_width();
+        let cells = width * wall.get_height();
+        let mask = |positions: &mut dyn Iterator<Item = (usize, usize)>| {
+            positions.fold(0u32, |m, (x, y)| m | 1 << (y * width + x))
+        };
+        let neighbors = (0..cells).map(|i| mask(&mut wall.neighbor_positions(i % width, i / width, rules))).collect::<Vec<u32>>();
+        let positions = (0..cells).map(|i| (i % width, i / width));
+        let rolls = mask(&mut positions.clone().filter(|&(x, y)| wall.get(x, y) == Some(Product::Paper)));
+        let empty = (0..cells).filter(|&i| rolls >> i & 1 == 0).collect::<Vec<usize>>();
+        let holds = |paper: u32| {
+            (0..cells).all(|i| paper >> i & 1 == 0 || (paper & neighbors[i]).count_ones() as usize >= rules.threshold)
+        };
+        let added = |subset: u32| {
+            let chosen = empty.iter().enumerate().filter(|(bit, _)| subset >> bit & 1 == 1);
+            chosen.fold(0, |m, (_, &i)| m | 1 << i)
+        };
+        let subsets = (0..1u32 << empty.len()).filter(|&subset| holds(rolls | added(subset)));
+        subsets.map(|subset| subset.count_ones() as usize).min()
+    }
+
+    #[test]
+    fn exact_reinforcement_is_minimal() {
+        for (wall, rules) in small_cases() {
+            let fewest = fewest_rolls_to_add(&wall, &rules);
+            match wall.reinforce(&rules) {
+                Ok(reinforcement) => {
+                    assert!(reinforcement.exact);
+                    assert_eq!(Some(reinforcement.added.len()), fewest, "{:?} {:?}", wall, rules);
+                    let reinforced = with_rolls_added(&wall, &reinforcement.added);
+                    assert_eq!(reinforced.count_accessible(&rules), 0, "{:?} {:?}", wall, rules);
+                }
+                Err(_) => assert_eq!(fewest, None, "{:?} {:?}", wall, rules),
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_reinforcement_leaves_nothing_accessible() {
+        let mut cases = small_cases();
+        // Large enough to skip the exact search.
+        let mut rng = fastrand::Rng::with_seed(141);
+        for toroidal in [false, true] {
+            for _ in 0..10 {
+                let rules = Rules { toroidal, threshold: rng.usize(1..=4), ..Rules::default() };
+                cases.push((random_wall(12, 10, rng.f64(), rng.u64(..)), rules));
+            }
+        }
+        for (wall, rules) in cases {
+            let Ok(region) = wall.reinforceable_region(&rules) else {
+                continue;
+            };
+            let mut grid = Grid::of_wall(&wall, &rules);
+            let added = grid.greedy_reinforcement(&region).into_iter().map(|i| grid.position(i)).collect::<Vec<_>>();
+            let reinforced = with_rolls_added(&wall, &added);
+            assert_eq!(reinforced.count_accessible(&rules), 0, "{:?} {:?}", wall, rules);
+            if wall.get_width() * wall.get_height() > super::EXACT_CELL_LIMIT {
+                let reinforcement = wall.reinforce(&rules).unwrap();
+                assert!(!reinforcement.exact);
+                assert_eq!(reinforcement.added, added);
+            }
+        }
+    }
+
+    #[test]
+    fn cascades_match_peeling_without_the_roll() {
+        for (wall, rules) in small_cases() {
+            let mut core = wall.clone();
+            core.peel(&rules);
+            let cascades = wall.rank_cascades(&rules);
+            let mut rolls = (0..core.get_height())
+                .flat_map(|y| (0..core.get_width()).map(move |x| (x, y)))
+                .filter(|&(x, y)| core.get(x, y) == Some(Product::Paper))
+                .collect::<Vec<(usize, usize)>>();
+            let mut ranked = cascades.iter().map(|cascade| cascade.position).collect::<Vec<(usize, usize)>>();
+            rolls.sort();
+            ranked.sort();
+            assert_eq!(ranked, rolls, "{:?} {:?}", wall, rules);
+            for cascade in &cascades {
+                let (x, y) = cascade.position;
+                let mut without = core.clone();
+                without.set(x, y, Product::Nothing);
+                assert_eq!(cascade.size, 1 + without.peel(&rules), "{:?} {:?} {:?}", wall, rules, cascade);
+            }
+            let order = cascades.iter().map(|c| (std::cmp::Reverse(c.size), c.position.1, c.position.0)).collect::<Vec<_>>();
+            assert!(order.is_sorted(), "{:?}", cascades);
+        }
+    }
+}
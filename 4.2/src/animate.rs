@@ -0,0 +1,144 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::wall::{Product, Wall};
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const PAPER: [u8; 3] = [40, 40, 40];
+// GIF palettes hold 256 colours; two go to the background and the remaining rolls.
+const MAX_WAVE_COLORS: usize = 254;
+
+/// The peeling process as frames: frame `f` shows the wall after `f` waves, with each removed
+/// roll coloured by the wave that removed it.
+#[derive(Debug)]
+pub struct Animation {
+    width: usize,
+    height: usize,
+    paper: Vec<bool>,
+    /// Wave that removed each cell's roll, indexed `y * width + x`.
+    removed_in: Vec<Option<usize>>,
+    wave_count: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Cell {
+    Empty,
+    Paper,
+    Removed(usize),
+}
+
+impl Animation {
+    pub fn new(wall: &Wall, waves: &[Vec<(usize, usize)>]) -> Self {
+        let (width, height) = (wall.get_width(), wall.get_height());
+        let mut paper = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                paper.push(wall.get(x, y) == Some(Product::Paper));
+            }
+        }
+        let mut removed_in = vec![None; width * height];
+        for (wave, positions) in waves.iter().enumerate() {
+            for &(x, y) in positions {
+                removed_in[y * width + x] = Some(wave);
+            }
+        }
+        Animation { width, height, paper, removed_in, wave_count: waves.len() }
+    }
+
+    fn cell(&self, index: usize, frame: usize) -> Cell {
+        match self.removed_in[index] {
+            Some(wave) if wave < frame => Cell::Removed(wave),
+            _ if self.paper[index] => Cell::Paper,
+            _ => Cell::Empty,
+        }
+    }
+
+    /// Colour of `wave`, running from red for the first wave to blue for the last.
+    fn wave_color(&self, wave: usize) -> [u8; 3] {
+        let hue = 240.0 * wave as f64 / self.wave_count.saturating_sub(1).max(1) as f64;
+        let sector = hue / 60.0;
+        let rising = (255.0 * (1.0 - (sector % 2.0 - 1.0).abs())) as u8;
+        match sector as usize {
+            0 => [255, rising, 0],
+            1 => [rising, 255, 0],
+            2 => [0, 255, rising],
+            3 => [0, rising, 255],
+            _ => [rising, 0, 255],
+        }
+    }
+
+    fn frame_count(&self) -> usize {
+        self.wave_count + 1
+    }
+
+    /// Plays the frames in the terminal, redrawing in place `fps` times a second.
+    pub fn play(&self, fps: f64) -> anyhow::Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        for frame in 0..self.frame_count() {
+            let mut screen = String::from("\x1b[H\x1b[2J");
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    match self.cell(y * self.width + x, frame) {
+                        Cell::Empty => screen.push('.'),
+                        Cell::Paper => screen.push('@'),
+                        Cell::Removed(wave) => {
+                            let [r, g, b] = self.wave_color(wave);
+                            screen.push_str(&format!("\x1b[38;2;{};{};{}m@\x1b[0m", r, g, b));
+                        }
+                    }
+                }
+                screen.push('\n');
+            }
+            screen.push_str(&format!("wave {}/{}\n", frame, self.wave_count));
+            stdout.write_all(screen.as_bytes())?;
+            stdout.flush()?;
+            std::thread::sleep(Duration::from_secs_f64(1.0 / fps));
+        }
+        Ok(())
+    }
+
+    /// Writes the frames as a looping animated GIF, drawing each cell as a `scale`-pixel square.
+    pub fn write_gif(&self, path: &Path, fps: f64, scale: usize) -> anyhow::Result<()> {
+        let (width, height) = (self.width * scale, self.height * scale);
+        if width == 0 || height == 0 {
+            return Err(anyhow::anyhow!("Wall is empty, so there is nothing to draw in a GIF"));
+        }
+        let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(anyhow::anyhow!("Wall is too large for a GIF at scale {}", scale));
+        };
+        let color_count = self.wave_count.clamp(1, MAX_WAVE_COLORS);
+        let mut palette = [BACKGROUND, PAPER].concat();
+        for color in 0..color_count {
+            palette.extend(self.wave_color(color * self.wave_count.max(1) / color_count));
+        }
+        let palette_index = |cell| match cell {
+            Cell::Empty => 0u8,
+            Cell::Paper => 1u8,
+            Cell::Removed(wave) => (2 + wave * color_count / self.wave_count) as u8,
+        };
+
+        let mut encoder = gif::Encoder::new(File::create(path)?, gif_width, gif_height, &palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        let mut pixels = vec![0u8; width * height];
+        for frame in 0..self.frame_count() {
+            for (row, line) in pixels.chunks_mut(width).enumerate() {
+                for (column, pixel) in line.iter_mut().enumerate() {
+                    let index = (row / scale) * self.width + column / scale;
+                    *pixel = palette_index(self.cell(index, frame));
+                }
+            }
+            let gif_frame = gif::Frame {
+                width: gif_width,
+                height: gif_height,
+                delay: (100.0 / fps).round() as u16,
+                buffer: Cow::Borrowed(&pixels),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&gif_frame)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use std::io::Write;
+
+use crate::rules::Rules;
+use crate::wall::{Product, Wall};
+
+/// A `width` by `height` wall in which each cell independently holds a roll with probability
+/// `density`. The same seed always gives the same wall.
+pub fn random_wall(width: usize, height: usize, density: f64, seed: u64) -> Wall {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let mut product = || if rng.f64() < density { Product::Paper } else { Product::Nothing };
+    let products = (0..height).map(|_| (0..width).map(|_| product()).collect());
+    Wall::from(products.collect::<Vec<Vec<Product>>>())
+}
+
+/// Random walls of one size, generated at each density from the same run of seeds.
+#[derive(Clone, Debug)]
+pub struct Experiment {
+    pub width: usize,
+    pub height: usize,
+    pub densities: Vec<f64>,
+    pub seeds: usize,
+    pub first_seed: u64,
+}
+
+/// Mean and sample variance of a list of counts.
+fn mean_and_variance(values: &[usize]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<usize>() as f64 / n;
+    if values.len() < 2 {
+        return (mean, 0.0);
+    }
+    let squares = values.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>();
+    (mean, squares / (n - 1.0))
+}
+
+impl Experiment {
+    /// Writes one CSV row per density with the mean and variance, over all seeds, of the
+    /// rolls accessible at the start and the rolls removed by peeling.
+    pub fn run(&self, rules: &Rules, mut out: impl Write) -> anyhow::Result<()> {
+        writeln!(out, "density,walls,accessible_mean,accessible_variance,removed_mean,removed_variance")?;
+        for &density in &self.densities {
+            let mut accessible = Vec::with_capacity(self.seeds);
+            let mut removed = Vec::with_capacity(self.seeds);
+            for seed in (self.first_seed..).take(self.seeds) {
+                let mut wall = random_wall(self.width, self.height, density, seed);
+                accessible.push(wall.count_accessible(rules));
+                removed.push(wall.peel(rules));
+            }
+            let (accessible_mean, accessible_variance) = mean_and_variance(&accessible);
+            let (removed_mean, removed_variance) = mean_and_variance(&removed);
+            writeln!(
+                out,
+                "{},{},{:.4},{:.4},{:.4},{:.4}",
+                density, self.seeds, accessible_mean, accessible_variance, removed_mean, removed_variance
+            )?;
+        }
+        Ok(())
+    }
+}
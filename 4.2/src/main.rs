@@ -1,113 +1,180 @@
+mod analysis;
+mod animate;
+mod bitmap;
+mod experiment;
+mod packed;
+mod rules;
+mod wall;
+
 use std::io::Read;
+use std::path::PathBuf;
 use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-enum Product {
-    Paper,
-    Nothing,
-}
-
-impl FromStr for Product {
-    type Err = anyhow::Error;
+use animate::Animation;
+use experiment::Experiment;
+use rules::{Neighborhood, Rules};
+use wall::Wall;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "@" => Ok(Product::Paper),
-            "." => Ok(Product::Nothing),
-            _ => Err(anyhow::anyhow!("Invalid product: {}", s)),
-        }
-    }
+#[derive(Debug)]
+struct Options {
+    rules: Rules,
+    waves: bool,
+    animate: bool,
+    gif: Option<PathBuf>,
+    fps: f64,
+    scale: usize,
+    input: Option<PathBuf>,
+    save: Option<PathBuf>,
+    analyze: bool,
+    top: usize,
+    random: Option<(usize, usize)>,
+    density: f64,
+    seed: u64,
+    experiment: Option<(usize, usize)>,
+    densities: Vec<f64>,
+    seeds: usize,
 }
 
-#[derive(Debug)]
-struct Wall {
-    products: Vec<Vec<Product>>,
+/// Parses a `WIDTHxHEIGHT` wall size.
+fn parse_size(s: &str) -> (usize, usize) {
+    let (width, height) = s.split_once('x').unwrap_or_else(|| panic!("invalid size: {}", s));
+    (width.parse::<usize>().unwrap(), height.parse::<usize>().unwrap())
 }
 
-impl Wall {
-    fn get(&self, x: usize, y: usize) -> Option<Product> {
-        self.products.get(y)?.get(x).copied()
+fn parse_args() -> Options {
+    let mut options = Options {
+        rules: Rules::default(),
+        waves: false,
+        animate: false,
+        gif: None,
+        fps: 10.0,
+        scale: 4,
+        input: None,
+        save: None,
+        analyze: false,
+        top: 10,
+        random: None,
+        density: 0.5,
+        seed: 0,
+        experiment: None,
+        densities: (1..20).map(|i| i as f64 / 20.0).collect(),
+        seeds: 100,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--neighborhood" => options.rules.neighborhood = Neighborhood::from_str(&value()).unwrap(),
+            "--torus" => options.rules.toroidal = true,
+            "--threshold" => options.rules.threshold = value().parse::<usize>().unwrap(),
+            "--input" => options.input = Some(value().into()),
+            "--save" => options.save = Some(value().into()),
+            "--waves" => options.waves = true,
+            "--animate" => options.animate = true,
+            "--gif" => options.gif = Some(value().into()),
+            "--fps" => options.fps = value().parse::<f64>().unwrap(),
+            "--scale" => options.scale = value().parse::<usize>().unwrap(),
+            "--analyze" => options.analyze = true,
+            "--top" => options.top = value().parse::<usize>().unwrap(),
+            "--random" => options.random = Some(parse_size(&value())),
+            "--density" => options.density = value().parse::<f64>().unwrap(),
+            "--seed" => options.seed = value().parse::<u64>().unwrap(),
+            "--experiment" => options.experiment = Some(parse_size(&value())),
+            "--densities" => options.densities = value().split(',').map(|d| d.parse::<f64>().unwrap()).collect(),
+            "--seeds" => options.seeds = value().parse::<usize>().unwrap(),
+            _ => panic!("unknown argument: {}", arg),
+        }
     }
+    assert!(options.scale >= 1, "--scale must be at least 1");
+    assert!(options.fps > 0.0 && options.fps.is_finite(), "--fps must be a positive number, not {}", options.fps);
+    options
+}
 
-    fn set(&mut self, x: usize, y: usize, product: Product) {
-        let slot: &mut Product = self.products.get_mut(y).unwrap().get_mut(x).unwrap();
-        *slot = product;
-    }
-    
-    fn get_width(&self) -> usize {
-        self.products.first().unwrap().len()
-    }
-    
-    fn get_height(&self) -> usize {
-        self.products.len()
+fn main() {
+    let options = parse_args();
+    if let Some((width, height)) = options.experiment {
+        assert!(options.seeds > 0, "--seeds must be at least 1");
+        options.rules.check(width, height).unwrap();
+        let experiment = Experiment {
+            width,
+            height,
+            densities: options.densities.clone(),
+            seeds: options.seeds,
+            first_seed: options.seed,
+        };
+        experiment.run(&options.rules, std::io::stdout().lock()).unwrap();
+        return;
     }
 
-    fn get_neighbors(&self, x: usize, y: usize) -> Vec<Option<Product>> {
-        let mut neighbors = Vec::new();
-        for i in vec![-1i32, 0i32, 1i32] {
-            for j in vec![-1i32, 0i32, 1i32] {
-                if i == 0i32 && j == 0i32 {
-                    continue;
-                }
-                let neighbor = self.get((x as i32 + i) as usize, (y as i32 + j) as usize);
-                neighbors.push(neighbor);
-            }
+    let mut wall = match (&options.input, options.random) {
+        (Some(path), _) => bitmap::load_wall(path).unwrap(),
+        (None, Some((width, height))) => experiment::random_wall(width, height, options.density, options.seed),
+        (None, None) => {
+            let mut input = String::new();
+            let _read_bytes = std::io::stdin().read_to_string(&mut input);
+            Wall::from_str(&input).unwrap()
         }
-        neighbors
-    }
+    };
+    options.rules.check(wall.get_width(), wall.get_height()).unwrap();
 
-    fn count_neighbors(&self, x: usize, y: usize) -> usize {
-        let neighbors = self.get_neighbors(x, y);
-        neighbors.iter().filter(|n| n.is_some() && **n != Some(Product::Nothing)).count()
+    if options.animate || options.gif.is_some() {
+        let initial = wall.clone();
+        let waves = wall.peel_waves(&options.rules);
+        let animation = Animation::new(&initial, &waves);
+        if options.animate {
+            animation.play(options.fps).unwrap();
+        }
+        if let Some(path) = &options.gif {
+            animation.write_gif(path, options.fps, options.scale).unwrap();
+            println!("wrote {} frames to {}", waves.len() + 1, path.display());
+        }
+        return;
     }
 
-    fn find_removable(&mut self) -> Option<(usize, usize)> {
-        for i in 0..self.get_width() {
-            for j in 0..self.get_height() {
-                if self.get(i, j) == Some(Product::Paper) && self.count_neighbors(i, j) < 4 {
-                    return Some((i, j));
-                }
-            }
-        }
-        None
+    if options.analyze {
+        analyze(&wall, &options);
+        return;
     }
-}
 
-impl FromStr for Wall {
-    type Err = anyhow::Error;
+    println!("wall: {:?}", wall);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lines = s.lines();
-        let mut products = Vec::new();
-        for line in lines {
-            let chars = line.chars().collect::<Vec<char>>();
-            let mut inner_products = Vec::new();
-            for char in chars {
-                let product = Product::from_str(&char.to_string()).unwrap();
-                inner_products.push(product);
-            }
-            products.push(inner_products);
+    if options.waves {
+        let waves = wall.peel_waves(&options.rules);
+        println!("waves: {}", waves.len());
+        for (i, removed) in waves.iter().enumerate() {
+            println!("wave {}: {}", i + 1, removed.len());
         }
+        println!("removable_count: {}", waves.iter().map(Vec::len).sum::<usize>());
+        print!("{}", wall);
+    } else {
+        let removable_count = wall.peel(&options.rules);
+        println!("removable_count: {}", removable_count);
+    }
 
-        Ok(Wall { products })
+    if let Some(path) = &options.save {
+        bitmap::save_wall(&wall, path).unwrap();
     }
 }
 
-fn main() {
-    let mut input = String::new();
-    let _read_bytes = std::io::stdin().read_to_string(&mut input);
-    let mut wall = Wall::from_str(&input).unwrap();
-    println!("wall: {:?}", wall);
+fn analyze(wall: &Wall, options: &Options) {
+    let cascades = wall.rank_cascades(&options.rules);
+    println!("stable_rolls: {}", cascades.len());
+    for cascade in cascades.iter().take(options.top) {
+        let (x, y) = cascade.position;
+        println!("x: {}, y: {}, cascade: {}", x, y, cascade.size);
+    }
 
-    let mut removable_count = 0;
-    loop {
-        let removable = wall.find_removable();
-        if removable.is_none() {
-            break;
+    match wall.reinforce(&options.rules) {
+        Ok(reinforcement) => {
+            let method = if reinforcement.exact { "exact" } else { "heuristic" };
+            println!("reinforcement: {} ({})", reinforcement.added.len(), method);
+            let mut reinforced = wall.clone();
+            for &(x, y) in &reinforcement.added {
+                println!("add x: {}, y: {}", x, y);
+                reinforced.set(x, y, wall::Product::Paper);
+            }
+            print!("{}", reinforced);
         }
-        removable_count += 1;
-        let (x, y) = removable.unwrap();
-        wall.set(x, y, Product::Nothing);
+        Err(e) => println!("reinforcement: impossible: {}", e),
     }
-    println!("removable_count: {}", removable_count);
 }
@@ -0,0 +1,94 @@
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Neighborhood {
+    /// The eight surrounding cells.
+    #[default]
+    Moore,
+    /// The four orthogonally adjacent cells.
+    VonNeumann,
+    /// Six cells of a hexagonal grid whose odd rows are shifted half a cell to the right.
+    Hexagonal,
+}
+
+impl FromStr for Neighborhood {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "moore" => Ok(Neighborhood::Moore),
+            "von-neumann" => Ok(Neighborhood::VonNeumann),
+            "hex" => Ok(Neighborhood::Hexagonal),
+            _ => Err(anyhow::anyhow!("Invalid neighborhood: {}", s)),
+        }
+    }
+}
+
+const MOORE: [(i32, i32); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+const VON_NEUMANN: [(i32, i32); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const HEX_EVEN_ROW: [(i32, i32); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+const HEX_ODD_ROW: [(i32, i32); 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+impl Neighborhood {
+    /// `(dx, dy)` offsets of the neighbours of a cell in row `y`.
+    pub fn offsets(&self, y: usize) -> &'static [(i32, i32)] {
+        match self {
+            Neighborhood::Moore => &MOORE,
+            Neighborhood::VonNeumann => &VON_NEUMANN,
+            Neighborhood::Hexagonal if y.is_multiple_of(2) => &HEX_EVEN_ROW,
+            Neighborhood::Hexagonal => &HEX_ODD_ROW,
+        }
+    }
+}
+
+/// Which cells count as neighbours and how few of them make a roll accessible.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    pub neighborhood: Neighborhood,
+    /// Whether the wall wraps around at its edges.
+    pub toroidal: bool,
+    /// A roll is accessible when fewer than this many neighbours hold paper.
+    pub threshold: usize,
+}
+
+impl Rules {
+    /// Rejects rules that do not make sense for a wall of `width` columns and `height` rows.
+    pub fn check(&self, width: usize, height: usize) -> anyhow::Result<()> {
+        // Below three cells across, a neighbour on one side wraps onto the one on the other
+        // side, or onto the cell itself, and would be counted twice.
+        if self.toroidal && (width < 3 || height < 3) {
+            return Err(anyhow::anyhow!("Toroidal walls need at least 3 columns and 3 rows, not {}x{}", width, height));
+        }
+        // Wrapping an odd number of offset rows would join two rows with the same shift,
+        // making the neighbour relation one-sided.
+        if self.toroidal && self.neighborhood == Neighborhood::Hexagonal && !height.is_multiple_of(2) {
+            return Err(anyhow::anyhow!("Toroidal hexagonal walls need an even row count, not {}", height));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules { neighborhood: Neighborhood::Moore, toroidal: false, threshold: 4 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Neighborhood, Rules};
+
+    #[test]
+    fn rejects_tori_that_wrap_onto_themselves() {
+        let flat = Rules::default();
+        let torus = Rules { toroidal: true, ..flat };
+        let hex_torus = Rules { neighborhood: Neighborhood::Hexagonal, ..torus };
+        assert!(flat.check(1, 1).is_ok());
+        assert!(torus.check(3, 3).is_ok());
+        for (width, height) in [(1, 1), (2, 5), (5, 2), (0, 0)] {
+            assert!(torus.check(width, height).is_err(), "{}x{}", width, height);
+        }
+        assert!(hex_torus.check(3, 4).is_ok());
+        assert!(hex_torus.check(4, 3).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use std::str::FromStr;
+
+use crate::packed::PackedRows;
+use crate::rules::Rules;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Product {
+    Paper,
+    Nothing,
+}
+
+impl FromStr for Product {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "@" => Ok(Product::Paper),
+            "." => Ok(Product::Nothing),
+            _ => Err(anyhow::anyhow!("Invalid product: {}", s)),
+        }
+    }
+}
+
+/// Stored one bit per cell, set for paper, so very large walls stay compact and whole rows
+/// of neighbour counts can be computed word by word.
+#[derive(Clone)]
+pub struct Wall {
+    paper: PackedRows,
+}
+
+impl Wall {
+    pub fn get(&self, x: usize, y: usize) -> Option<Product> {
+        let paper = self.paper.get(x, y)?;
+        Some(if paper { Product::Paper } else { Product::Nothing })
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, product: Product) {
+        self.paper.set(x, y, product == Product::Paper);
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.paper.width()
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.paper.height()
+    }
+
+    /// Position `offset` away from `(x, y)`, wrapping around the edges of a toroidal wall, or
+    /// `None` if it falls off the wall.
+    fn offset(
+        &self,
+        x: usize,
+        y: usize,
+        (dx, dy): (i32, i32),
+        rules: &Rules,
+    ) -> Option<(usize, usize)> {
+        let (width, height) = (self.get_width() as i64, self.get_height() as i64);
+        let (nx, ny) = (x as i64 + dx as i64, y as i64 + dy as i64);
+        if rules.toroidal {
+            return Some((nx.rem_euclid(width) as usize, ny.rem_euclid(height) as usize));
+        }
+        if nx < 0 || ny < 0 || nx >= width || ny >= height {
+            return None;
+        }
+        Some((nx as usize, ny as usize))
+    }
+
+    /// Number of paper neighbours of `(x, y)`.
+    pub fn count_neighbors(&self, x: usize, y: usize, rules: &Rules) -> usize {
+        let neighbors = self.neighbor_positions(x, y, rules);
+        neighbors.filter(|&(nx, ny)| self.get(nx, ny) == Some(Product::Paper)).count()
+    }
+
+    /// `count_neighbors` for every cell of row `y` at once.
+    pub fn count_row_neighbors(&self, y: usize, rules: &Rules) -> Vec<u8> {
+        self.paper.neighbor_counts(y, rules.neighborhood.offsets(y), rules.toroidal)
+    }
+
+    /// On-wall positions of the neighbours of `(x, y)`.
+    pub fn neighbor_positions(
+        &self,
+        x: usize,
+        y: usize,
+        rules: &Rules,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let rules = *rules;
+        let offsets = rules.neighborhood.offsets(y).iter();
+        offsets.filter_map(move |&offset| self.offset(x, y, offset, &rules))
+    }
+
+    /// Neighbour counts of every roll, indexed `y * width + x`, and the rolls that are already
+    /// accessible, which are marked in `queued`.
+    fn initial_accessible(
+        &self,
+        queued: &mut [bool],
+        rules: &Rules,
+    ) -> (Vec<usize>, Vec<(usize, usize)>) {
+        let width = self.get_width();
+        let mut counts = vec![0usize; queued.len()];
+        let mut accessible = Vec::new();
+        for y in 0..self.get_height() {
+            let row_counts = self.count_row_neighbors(y, rules);
+            for x in 0..width {
+                if self.get(x, y) != Some(Product::Paper) {
+                    continue;
+                }
+                counts[y * width + x] = row_counts[x] as usize;
+                if counts[y * width + x] < rules.threshold {
+                    queued[y * width + x] = true;
+                    accessible.push((x, y));
+                }
+            }
+        }
+        (counts, accessible)
+    }
+
+    /// Number of rolls accessible before any are removed, the count asked for in part one.
+    pub fn count_accessible(&self, rules: &Rules) -> usize {
+        let mut queued = vec![false; self.get_width() * self.get_height()];
+        self.initial_accessible(&mut queued, rules).1.len()
+    }
+
+    /// Lowers the counts around a removed roll, pushing rolls that just became accessible.
+    fn release_neighbors(
+        &self,
+        (x, y): (usize, usize),
+        counts: &mut [usize],
+        queued: &mut [bool],
+        accessible: &mut Vec<(usize, usize)>,
+        rules: &Rules,
+    ) {
+        for (nx, ny) in self.neighbor_positions(x, y, rules) {
+            let index = ny * self.get_width() + nx;
+            if self.get(nx, ny) != Some(Product::Paper) {
+                continue;
+            }
+            counts[index] -= 1;
+            if counts[index] < rules.threshold && !queued[index] {
+                queued[index] = true;
+                accessible.push((nx, ny));
+            }
+        }
+    }
+
+    /// Removes every roll that is, or becomes, reachable with fewer than `rules.threshold`
+    /// neighbours and returns how many were removed. Only neighbours of removed rolls are
+    /// re-examined, since removing a roll can only lower its neighbours' counts, so the whole
+    /// peel is O(cells).
+    pub fn peel(&mut self, rules: &Rules) -> usize {
+        let mut queued = vec![false; self.get_width() * self.get_height()];
+        let (mut counts, mut accessible) = self.initial_accessible(&mut queued, rules);
+        let mut removed = 0;
+        while let Some((x, y)) = accessible.pop() {
+            self.set(x, y, Product::Nothing);
+            removed += 1;
+            self.release_neighbors((x, y), &mut counts, &mut queued, &mut accessible, rules);
+        }
+        removed
+    }
+
+    /// Peels in forklift passes: each wave removes every roll accessible when it starts.
+    /// Returns the positions of the rolls removed in each wave.
+    pub fn peel_waves(&mut self, rules: &Rules) -> Vec<Vec<(usize, usize)>> {
+        let mut queued = vec![false; self.get_width() * self.get_height()];
+        let (mut counts, mut wave) = self.initial_accessible(&mut queued, rules);
+        let mut waves = Vec::new();
+        while !wave.is_empty() {
+            for &(x, y) in &wave {
+                self.set(x, y, Product::Nothing);
+            }
+            let mut next_wave = Vec::new();
+            for &position in &wave {
+                self.release_neighbors(position, &mut counts, &mut queued, &mut next_wave, rules);
+            }
+            waves.push(std::mem::replace(&mut wave, next_wave));
+        }
+        waves
+    }
+}
+
+impl std::fmt::Display for Wall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.get_height() {
+            for x in 0..self.get_width() {
+                let c = match self.get(x, y) {
+                    Some(Product::Paper) => '@',
+                    _ => '.',
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Wall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows = self.to_string();
+        f.debug_struct("Wall").field("rows", &rows.lines().collect::<Vec<&str>>()).finish()
+    }
+}
+
+impl From<Vec<Vec<Product>>> for Wall {
+    fn from(products: Vec<Vec<Product>>) -> Self {
+        let width = products.iter().map(Vec::len).max().unwrap_or(0);
+        let mut paper = PackedRows::new(width, products.len());
+        for (y, row) in products.iter().enumerate() {
+            for (x, product) in row.iter().enumerate() {
+                paper.set(x, y, *product == Product::Paper);
+            }
+        }
+        Wall { paper }
+    }
+}
+
+impl FromStr for Wall {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines();
+        let mut products = Vec::new();
+        for line in lines {
+            let chars = line.chars().collect::<Vec<char>>();
+            let mut inner_products = Vec::new();
+            for char in chars {
+                let product = Product::from_str(&char.to_string()).unwrap();
+                inner_products.push(product);
+            }
+            products.push(inner_products);
+        }
+
+        Ok(Wall::from(products))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Product, Wall};
+    use crate::experiment::random_wall;
+    use crate::rules::{Neighborhood, Rules};
+
+    /// Removes the first accessible roll in scan order, rescanning the whole wall after every
+    /// removal, the way rolls were peeled before the worklist.
+    fn peel_by_rescanning(wall: &mut Wall, rules: &Rules) -> usize {
+        let mut removed = 0;
+        loop {
+            let positions = (0..wall.get_height()).flat_map(|y| (0..wall.get_width()).map(move |x| (x, y)));
+            let mut accessible = positions.filter(|&(x, y)| {
+                wall.get(x, y) == Some(Product::Paper) && wall.count_neighbors(x, y, rules) < rules.threshold
+            });
+            let Some((x, y)) = accessible.next() else {
+                return removed;
+            };
+            wall.set(x, y, Product::Nothing);
+            removed += 1;
+        }
+    }
+
+    /// Random walls of up to 8x8 for every neighbourhood, with and without wrap-around.
+    fn random_cases() -> Vec<(Wall, Rules)> {
+        let mut rng = fastrand::Rng::with_seed(35);
+        let mut cases = Vec::new();
+        for neighborhood in [Neighborhood::Moore, Neighborhood::VonNeumann, Neighborhood::Hexagonal] {
+            for toroidal in [false, true] {
+                for _ in 0..200 {
+                    let (width, height) = (rng.usize(1..=8), rng.usize(1..=8));
+                    let rules = Rules { neighborhood, toroidal, threshold: rng.usize(1..=6) };
+                    if rules.check(width, height).is_ok() {
+                        cases.push((random_wall(width, height, rng.f64(), rng.u64(..)), rules));
+                    }
+                }
+            }
+        }
+        cases
+    }
+
+    #[test]
+    fn peel_matches_rescanning() {
+        for (wall, rules) in random_cases() {
+            let (mut peeled, mut rescanned) = (wall.clone(), wall.clone());
+            let removed = peeled.peel(&rules);
+            assert_eq!(removed, peel_by_rescanning(&mut rescanned, &rules), "{:?} {:?}", wall, rules);
+            assert_eq!(peeled.to_string(), rescanned.to_string(), "{:?} {:?}", wall, rules);
+        }
+    }
+
+    #[test]
+    fn waves_add_up_to_the_peel() {
+        for (wall, rules) in random_cases() {
+            let (mut peeled, mut waved) = (wall.clone(), wall.clone());
+            let removed = peeled.peel(&rules);
+            let waves = waved.peel_waves(&rules);
+            assert!(waves.iter().all(|wave| !wave.is_empty()), "{:?} {:?}", wall, rules);
+            assert_eq!(waves.iter().map(Vec::len).sum::<usize>(), removed, "{:?} {:?}", wall, rules);
+            assert_eq!(waved.to_string(), peeled.to_string(), "{:?} {:?}", wall, rules);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::anyhow;
+use memmap2::Mmap;
+
+use crate::range::Range;
+use crate::range_set::RangeSet;
+
+const MAGIC: &[u8; 8] = b"D5RANGES";
+// Bump whenever the layout below changes, so older files are rejected rather than misread.
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 32;
+const RECORD_LEN: usize = 24;
+
+// Layout, all integers little-endian:
+//   header:  magic (8 bytes), version (u32), reserved (u32), range count (u64), checksum (u64)
+//   records: start (u64), end (u64), IDs covered by all earlier ranges (u64), sorted by start
+
+// FNV-1a over the records, so a truncated or altered file is caught when it is opened.
+fn checksum(records: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in records {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Writes the merged ranges of `set` as an index file that `Index::open` can map.
+pub fn write_index(set: &RangeSet, path: &Path) -> anyhow::Result<()> {
+    let mut records = Vec::with_capacity(set.iter().len() * RECORD_LEN);
+    // Only the running total after the last range can exceed a u64, and it is not stored.
+    let mut before = 0u128;
+    for range in set {
+        for field in [range.start, range.end, before as u64] {
+            records.extend(field.to_le_bytes());
+        }
+        before += range.len();
+    }
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(MAGIC)?;
+    file.write_all(&VERSION.to_le_bytes())?;
+    file.write_all(&0u32.to_le_bytes())?;
+    file.write_all(&(set.iter().len() as u64).to_le_bytes())?;
+    file.write_all(&checksum(&records).to_le_bytes())?;
+    file.write_all(&records)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// A memory-mapped index file, queried in place with binary searches over its records.
+#[derive(Debug)]
+pub struct Index {
+    map: Mmap,
+    count: usize,
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+impl Index {
+    /// Maps the file at `path`, rejecting it if it is not an index of this version or its
+    /// records do not match the checksum.
+    pub fn open(path: &Path) -> anyhow::Result<Index> {
+        let file = File::open(path)?;
+        // SAFETY: the map is only read, and index files are not modified while in use.
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER_LEN || &map[..8] != MAGIC {
+            return Err(anyhow!("{} is not a range index", path.display()));
+        }
+        let version = u32::from_le_bytes(map[8..12].try_into().unwrap());
+        if version != VERSION {
+            return Err(anyhow!("{} has index version {}, expected {}", path.display(), version, VERSION));
+        }
+        let count = read_u64(&map, 16) as usize;
+        let expected_len = count.checked_mul(RECORD_LEN).and_then(|len| len.checked_add(HEADER_LEN));
+        if expected_len != Some(map.len()) {
+            return Err(anyhow!("{} is {} bytes, expected {} ranges", path.display(), map.len(), count));
+        }
+        if checksum(&map[HEADER_LEN..]) != read_u64(&map, 24) {
+            return Err(anyhow!("{} does not match its checksum", path.display()));
+        }
+        Ok(Index { map, count })
+    }
+
+    pub fn range_count(&self) -> usize {
+        self.count
+    }
+
+    pub fn range(&self, index: usize) -> Range {
+        let offset = HEADER_LEN + index * RECORD_LEN;
+        Range { start: read_u64(&self.map, offset), end: read_u64(&self.map, offset + 8) }
+    }
+
+    /// IDs covered by the ranges before the one at `index`.
+    fn covered_before(&self, index: usize) -> u128 {
+        if index == self.count {
+            return self.total_length();
+        }
+        read_u64(&self.map, HEADER_LEN + index * RECORD_LEN + 16) as u128
+    }
+
+    /// Number of leading ranges for which `predicate` holds, which must be all of them up to
+    /// some point.
+    fn partition_point(&self, predicate: impl Fn(Range) -> bool) -> usize {
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let middle = (low + high) / 2;
+            if predicate(self.range(middle)) {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        let index = self.partition_point(|r| r.end < value);
+        index < self.count && self.range(index).contains(value)
+    }
+
+    pub fn total_length(&self) -> u128 {
+        match self.count {
+            0 => 0,
+            count => self.covered_before(count - 1) + self.range(count - 1).len(),
+        }
+    }
+
+    /// Number of IDs within `bounds` that the ranges cover, from the running totals of the
+    /// ranges at either end.
+    pub fn coverage(&self, bounds: Range) -> u128 {
+        let first = self.partition_point(|r| r.end < bounds.start);
+        let last = self.partition_point(|r| r.start <= bounds.end);
+        if first >= last {
+            return 0;
+        }
+        let whole = self.covered_before(last) - self.covered_before(first);
+        let (head, tail) = (self.range(first), self.range(last - 1));
+        let cut = bounds.start.saturating_sub(head.start) + tail.end.saturating_sub(bounds.end);
+        whole - cut as u128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{write_index, Index, HEADER_LEN};
+    use crate::range::Range;
+    use crate::range_set::RangeSet;
+
+    fn index_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("fiveone-{}-{}.idx", std::process::id(), name))
+    }
+
+    /// IDs of `set` within `bounds`, counted range by range.
+    fn covered(set: &RangeSet, bounds: Range) -> u128 {
+        let clipped = set.iter().map(|r| Range { start: r.start.max(bounds.start), end: r.end.min(bounds.end) });
+        clipped.map(|r| r.len()).sum()
+    }
+
+    fn assert_round_trip(set: &RangeSet, probes: &[u64], name: &str) {
+        let path = index_path(name);
+        write_index(set, &path).unwrap();
+        let index = Index::open(&path).unwrap();
+        assert_eq!(index.range_count(), set.iter().len());
+        assert_eq!(index.total_length(), set.total_length());
+        for &value in probes {
+            assert_eq!(index.contains(value), set.contains(value), "{} in {:?}", value, set);
+        }
+        for &start in probes {
+            for &end in probes.iter().filter(|&&end| end >= start) {
+                let bounds = Range { start, end };
+                assert_eq!(index.coverage(bounds), covered(set, bounds), "{} of {:?}", bounds, set);
+            }
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn answers_like_the_set_it_was_written_from() {
+        let mut rng = fastrand::Rng::with_seed(49);
+        let probes = (0..40).collect::<Vec<u64>>();
+        for _ in 0..100 {
+            let ranges = (0..rng.usize(..8)).map(|_| {
+                let start = rng.u64(..40);
+                Range { start, end: rng.u64(start..40) }
+            });
+            assert_round_trip(&ranges.collect(), &probes, "random");
+        }
+
+        let top = u64::MAX;
+        let edges = [0, 1, 5, 6, 7, top - 4, top - 3, top - 1, top];
+        assert_round_trip(&RangeSet::new(), &edges, "empty");
+        let ends_at_max = [Range { start: 1, end: 5 }, Range { start: top - 3, end: top }];
+        assert_round_trip(&ends_at_max.into_iter().collect(), &edges, "ends-at-max");
+        assert_round_trip(&[Range { start: 0, end: top }].into_iter().collect(), &edges, "everything");
+    }
+
+    /// Writes a small index, spoils its bytes with `spoil` and returns why opening it failed.
+    fn open_spoiled(name: &str, spoil: impl FnOnce(&mut Vec<u8>)) -> String {
+        let path = index_path(name);
+        let set = [Range { start: 3, end: 5 }, Range { start: 10, end: 14 }].into_iter().collect::<RangeSet>();
+        write_index(&set, &path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        spoil(&mut bytes);
+        std::fs::write(&path, bytes).unwrap();
+        let error = Index::open(&path).unwrap_err().to_string();
+        std::fs::remove_file(path).unwrap();
+        error
+    }
+
+    #[test]
+    fn rejects_damaged_files() {
+        let flipped = open_spoiled("flipped", |bytes| bytes[HEADER_LEN + 9] ^= 1);
+        assert!(flipped.contains("does not match its checksum"), "{}", flipped);
+        let truncated = open_spoiled("truncated", |bytes| bytes.truncate(bytes.len() - 1));
+        assert!(truncated.contains("expected 2 ranges"), "{}", truncated);
+        let header_only = open_spoiled("header-only", |bytes| bytes.truncate(HEADER_LEN - 1));
+        assert!(header_only.contains("is not a range index"), "{}", header_only);
+        let bumped = open_spoiled("bumped", |bytes| bytes[8] += 1);
+        assert!(bumped.contains("has index version 2, expected 1"), "{}", bumped);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::range::Range;
+
+/// A range as it appeared in the input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    pub range: Range,
+    /// 1-based line of the input the range was read from.
+    pub line: usize,
+}
+
+/// Unmerged ranges sorted by start and laid out as an implicit balanced tree: the root of
+/// any slice is its middle entry. Each node also records the largest end in its subtree,
+/// so a lookup skips subtrees that end before the value and visits only the entries that
+/// could hold it.
+#[derive(Debug)]
+pub struct IntervalTree {
+    entries: Vec<Entry>,
+    max_ends: Vec<u64>,
+}
+
+impl IntervalTree {
+    pub fn new(mut entries: Vec<Entry>) -> Self {
+        entries.sort_by_key(|entry| (entry.range.start, entry.line));
+        let mut tree = IntervalTree { max_ends: vec![0; entries.len()], entries };
+        tree.fill_max_ends(0, tree.entries.len());
+        tree
+    }
+
+    fn fill_max_ends(&mut self, low: usize, high: usize) -> u64 {
+        if low >= high {
+            return 0;
+        }
+        let middle = (low + high) / 2;
+        let left = self.fill_max_ends(low, middle);
+        let right = self.fill_max_ends(middle + 1, high);
+        self.max_ends[middle] = self.entries[middle].range.end.max(left).max(right);
+        self.max_ends[middle]
+    }
+
+    /// Every entry whose range holds `value`, in order of range start.
+    pub fn containing(&self, value: u64) -> Vec<&Entry> {
+        let mut found = Vec::new();
+        self.collect(value, 0, self.entries.len(), &mut found);
+        found
+    }
+
+    fn collect<'a>(&'a self, value: u64, low: usize, high: usize, found: &mut Vec<&'a Entry>) {
+        if low >= high {
+            return;
+        }
+        let middle = (low + high) / 2;
+        if self.max_ends[middle] < value {
+            return;
+        }
+        self.collect(value, low, middle, found);
+        let entry = &self.entries[middle];
+        // Entries to the right start no earlier than this one, so none can hold `value`.
+        if entry.range.start > value {
+            return;
+        }
+        if entry.range.contains(value) {
+            found.push(entry);
+        }
+        self.collect(value, middle + 1, high, found);
+    }
+}
@@ -1,78 +1,142 @@
-use anyhow::anyhow;
+mod index;
+mod interval_tree;
+mod parse;
+mod range;
+mod range_set;
+
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use index::Index;
+use interval_tree::{Entry, IntervalTree};
+use parse::{read_database, read_ids, Database, ParseError};
+use range::Range;
+use range_set::RangeSet;
+
 #[derive(Debug)]
-struct Range {
-    start: u64,
-    end: u64,
+struct Options {
+    /// How many values to check between progress reports on stderr.
+    report_every: u64,
+    /// Whether to list the ranges holding each value and how many values each range held.
+    explain: bool,
+    /// Where to write the merged ranges as a binary index instead of checking IDs.
+    compile: Option<PathBuf>,
+    /// A binary index to check IDs against, in which case stdin holds only IDs.
+    index: Option<PathBuf>,
+    /// Range to report the indexed coverage of.
+    coverage: Option<Range>,
 }
 
-impl FromStr for Range {
-    type Err = std::num::ParseIntError;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let strs = s.split("-").collect::<Vec<&str>>();
-        let start = strs[0].parse::<u64>()?;
-        let end = strs[1].parse::<u64>()?;
-        Ok(Range { start, end })
+fn parse_args() -> Options {
+    let mut options = Options {
+        report_every: 1_000_000,
+        explain: false,
+        compile: None,
+        index: None,
+        coverage: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--report-every" => options.report_every = value().parse::<u64>().unwrap(),
+            "--explain" => options.explain = true,
+            "--compile" => options.compile = Some(value().into()),
+            "--index" => options.index = Some(value().into()),
+            "--coverage" => options.coverage = Some(Range::from_str(&value()).unwrap()),
+            _ => panic!("unknown argument: {}", arg),
+        }
     }
+    options
 }
 
-impl Range {
-    fn contains(&self, value: u64) -> bool {
-        value >= self.start && value <= self.end
+fn main() {
+    let options = parse_args();
+    if let Some(path) = &options.index {
+        query_index(path, &options);
+        return;
+    }
+    let Database { ranges, ids } = or_exit(read_database(get_lines()));
+    if let Some(path) = &options.compile {
+        let fresh_ranges = ranges.into_iter().map(|(_, range)| range).collect::<RangeSet>();
+        index::write_index(&fresh_ranges, path).unwrap();
+        println!("compiled {} ranges to {}", fresh_ranges.iter().len(), path.display());
+        return;
+    }
+    println!("ranges: {:?}", ranges.iter().map(|&(_, range)| range).collect::<Vec<Range>>());
+    if options.explain {
+        explain(&ranges, ids.map(or_exit));
+        return;
     }
+    let fresh_ranges = ranges.into_iter().map(|(_, range)| range).collect::<RangeSet>();
+    count_fresh(ids.map(or_exit), |value| fresh_ranges.contains(value), &options);
 }
 
-fn main() {
-    let mut lines = get_lines();
-    let ranges = get_ranges(&mut lines);
-    let values = get_values(&mut lines);
-    println!("ranges: {:?}", ranges);
-    println!("values: {:?}", values);
-    let mut fresh = 0;
+/// Answers queries from a compiled index without reading any ranges as text.
+fn query_index(path: &Path, options: &Options) {
+    let index = Index::open(path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    if let Some(bounds) = options.coverage {
+        println!("ranges: {}", index.range_count());
+        println!("covered: {} of {}", index.coverage(bounds), bounds.len());
+        return;
+    }
+    count_fresh(read_ids(get_lines()).map(or_exit), |value| index.contains(value), options);
+}
+
+/// Checks values as they are read, so memory does not grow with the feed.
+fn count_fresh(values: impl Iterator<Item = u64>, is_fresh: impl Fn(u64) -> bool, options: &Options) {
+    let (mut checked, mut fresh) = (0u64, 0u64);
     for value in values {
-        if is_contained(value, &ranges) {
+        checked += 1;
+        if is_fresh(value) {
             fresh += 1;
         }
+        if options.report_every > 0 && checked.is_multiple_of(options.report_every) {
+            eprintln!("progress: {} checked, {} fresh", checked, fresh);
+        }
     }
+    println!("checked: {}", checked);
     println!("fresh: {}", fresh);
 }
 
-fn get_ranges(lines: &mut impl Iterator<Item = String>) -> Vec<Range> {
-    let mut ranges = Vec::new();
-    for line in lines {
-        let range_result = Range::from_str(&line);
-        match range_result {
-            Ok(range) => ranges.push(range),
-            Err(e) => return ranges
-        }
-    }
-    panic!("Oops all ranges!");
+fn or_exit<T>(result: Result<T, ParseError>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("invalid database {}", e);
+        std::process::exit(1);
+    })
 }
 
-fn get_values(lines: impl Iterator<Item = String>) -> Vec<u64> {
-    let mut values = Vec::new();
-    for line in lines {
-        let value_result = u64::from_str(&line);
-        match value_result {
-            Ok(value) => values.push(value),
-            Err(_) => return values
+/// Lists every range holding each value, then how many values each range held.
+fn explain(ranges: &[(usize, Range)], values: impl Iterator<Item = u64>) {
+    let entries = ranges.iter().map(|&(line, range)| Entry { range, line });
+    let tree = IntervalTree::new(entries.collect());
+    // Indexed by line number.
+    let mut hits = vec![0u64; ranges.last().map_or(0, |&(line, _)| line + 1)];
+    let mut fresh = 0;
+    for value in values {
+        let containing = tree.containing(value);
+        if containing.is_empty() {
+            println!("{}: spoiled", value);
+            continue;
         }
-    }
-    values
-}
-
-fn is_contained(value: u64, ranges: &Vec<Range>) -> bool {
-    for range in ranges {
-        if range.contains(value) {
-            return true;
+        fresh += 1;
+        let mut lines = Vec::new();
+        for entry in containing {
+            hits[entry.line] += 1;
+            lines.push(format!("line {} ({})", entry.line, entry.range));
         }
+        println!("{}: {}", value, lines.join(", "));
+    }
+    for &(line, range) in ranges {
+        println!("line {} ({}): {} hits", line, range, hits[line]);
     }
-    false
+    println!("fresh: {}", fresh);
 }
 
 fn get_lines() -> impl Iterator<Item = String> {
     let stdin = std::io::stdin();
-    let lines = stdin.lines().map(|line| line.unwrap());
-    lines
-}
\ No newline at end of file
+    stdin.lines().map(|line| line.unwrap())
+}
@@ -0,0 +1,132 @@
+use std::str::FromStr;
+
+use crate::range::Range;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// What a line holds once a `#` comment and surrounding whitespace are stripped.
+#[derive(Debug, PartialEq)]
+enum Content<'a> {
+    /// Nothing at all. After the first range this separates the sections; before it, it is
+    /// skipped like a comment.
+    Blank,
+    /// Only a comment, skipped in either section.
+    Comment,
+    Text(&'a str),
+}
+
+fn content(line: &str) -> Content<'_> {
+    let (text, comment) = match line.split_once('#') {
+        Some((text, _)) => (text.trim(), true),
+        None => (line.trim(), false),
+    };
+    match (text.is_empty(), comment) {
+        (true, false) => Content::Blank,
+        (true, true) => Content::Comment,
+        (false, _) => Content::Text(text),
+    }
+}
+
+/// A database read up to its IDs, which are left to be streamed.
+#[derive(Debug)]
+pub struct Database<I> {
+    /// Each range with the 1-based line it was read from.
+    pub ranges: Vec<(usize, Range)>,
+    pub ids: Ids<I>,
+}
+
+/// Reads the ranges section of a database: `start-end` lines up to the first blank line after
+/// a range, or to the end of a file that holds only ranges.
+pub fn read_database<I: Iterator<Item = String>>(lines: I) -> Result<Database<I>, ParseError> {
+    let mut lines = lines.enumerate();
+    let mut ranges = Vec::new();
+    for (index, line) in lines.by_ref() {
+        let text = match content(&line) {
+            Content::Blank if !ranges.is_empty() => break,
+            Content::Blank | Content::Comment => continue,
+            Content::Text(text) => text,
+        };
+        let error = |message| ParseError { line: index + 1, message };
+        if u64::from_str(text).is_ok() {
+            let message = format!("expected a range, found {:?}; is the blank line before the IDs missing?", text);
+            return Err(error(message));
+        }
+        let range = Range::from_str(text).map_err(|e| error(e.to_string()))?;
+        ranges.push((index + 1, range));
+    }
+    Ok(Database { ranges, ids: Ids { lines, failed: false } })
+}
+
+/// Reads IDs alone, without a ranges section before them.
+pub fn read_ids<I: Iterator<Item = String>>(lines: I) -> Ids<I> {
+    Ids { lines: lines.enumerate(), failed: false }
+}
+
+/// The IDs section, one ID per line, read lazily so that memory does not grow with it.
+/// Yields an error for the first line that is not an ID and stops there.
+#[derive(Debug)]
+pub struct Ids<I> {
+    lines: std::iter::Enumerate<I>,
+    failed: bool,
+}
+
+impl<I: Iterator<Item = String>> Iterator for Ids<I> {
+    type Item = Result<u64, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        for (index, line) in self.lines.by_ref() {
+            let Content::Text(text) = content(&line) else { continue };
+            let id = u64::from_str(text).map_err(|_| ParseError {
+                line: index + 1,
+                message: format!("invalid ID {:?}", text),
+            });
+            self.failed = id.is_err();
+            return Some(id);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_database, ParseError};
+
+    /// Ranges as `(line, "start-end")`, and the IDs.
+    type Parsed = (Vec<(usize, String)>, Vec<u64>);
+
+    fn parse(input: &str) -> Result<Parsed, ParseError> {
+        let database = read_database(input.lines().map(String::from))?;
+        let ranges = database.ranges.iter().map(|(line, range)| (*line, range.to_string())).collect();
+        Ok((ranges, database.ids.collect::<Result<Vec<u64>, ParseError>>()?))
+    }
+
+    #[test]
+    fn skips_blank_lines_before_the_first_range() {
+        let expected = vec![(4, "3-5".to_string()), (5, "10-14".to_string())];
+        assert_eq!(parse("\n# fresh ranges\n\n3-5\n10-14\n\n4\n"), Ok((expected, vec![4])));
+        assert_eq!(parse("  \n\n3-5\n\n5\n6\n"), Ok((vec![(3, "3-5".to_string())], vec![5, 6])));
+    }
+
+    #[test]
+    fn splits_the_sections_at_a_blank_line() {
+        let expected = vec![(1, "3-5".to_string()), (3, "10-14".to_string())];
+        assert_eq!(parse("3-5\n# comment\n10-14\n\n\n4\n  # last\n11\n"), Ok((expected, vec![4, 11])));
+        assert_eq!(parse("3-5\n10-14\n").map(|(ranges, _)| ranges.len()), Ok(2));
+        assert_eq!(parse("\n4\n").unwrap_err().line, 2);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::range::Range;
+
+/// A set of IDs stored as sorted, disjoint ranges. Ranges that overlap or touch are merged
+/// as they are inserted, so every set has exactly one representation.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RangeSet {
+    ranges: Vec<Range>,
+}
+
+impl RangeSet {
+    pub fn new() -> Self {
+        RangeSet::default()
+    }
+
+    /// Adds every ID in `range`, joining it with any stored ranges it touches.
+    pub fn insert(&mut self, range: Range) {
+        if range.is_empty() {
+            return;
+        }
+        // Stored ranges before `first` end too early to join `range`.
+        let first = self.ranges.partition_point(|r| r.end.saturating_add(1) < range.start);
+        let mut joined = range;
+        let mut last = first;
+        while last < self.ranges.len() && self.ranges[last].touches(&joined) {
+            joined = joined.join(&self.ranges[last]);
+            last += 1;
+        }
+        self.ranges.splice(first..last, [joined]);
+    }
+
+    /// Removes every ID in `range`, splitting a stored range that straddles it.
+    pub fn remove(&mut self, range: Range) {
+        if range.is_empty() {
+            return;
+        }
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        if first >= last {
+            return;
+        }
+        let (head, tail) = (self.ranges[first], self.ranges[last - 1]);
+        let mut kept = Vec::new();
+        if head.start < range.start {
+            kept.push(Range { start: head.start, end: range.start - 1 });
+        }
+        if tail.end > range.end {
+            kept.push(Range { start: range.end + 1, end: tail.end });
+        }
+        self.ranges.splice(first..last, kept);
+    }
+
+    /// Binary searches for the one range that could hold `value`.
+    pub fn contains(&self, value: u64) -> bool {
+        self.index_of(value).is_some()
+    }
+
+    /// Position in `iter` order of the range holding `value`.
+    pub fn index_of(&self, value: u64) -> Option<usize> {
+        let index = self.ranges.partition_point(|r| r.end < value);
+        self.ranges.get(index).is_some_and(|r| r.contains(value)).then_some(index)
+    }
+
+    /// The disjoint ranges in ascending order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Range> {
+        self.ranges.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of IDs in the set.
+    pub fn total_length(&self) -> u128 {
+        self.ranges.iter().map(Range::len).sum()
+    }
+}
+
+impl FromIterator<Range> for RangeSet {
+    fn from_iter<I: IntoIterator<Item = Range>>(ranges: I) -> Self {
+        let mut set = RangeSet::new();
+        set.extend(ranges);
+        set
+    }
+}
+
+impl Extend<Range> for RangeSet {
+    fn extend<I: IntoIterator<Item = Range>>(&mut self, ranges: I) {
+        for range in ranges {
+            self.insert(range);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a RangeSet {
+    type Item = &'a Range;
+    type IntoIter = std::slice::Iter<'a, Range>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeSet;
+    use crate::range::Range;
+
+    const UNIVERSE: usize = 64;
+
+    /// Applies random inserts and removes within the IDs `base..base + 64` to a set and to one
+    /// flag per ID, checking after every step that the two agree.
+    fn check_against_flags(base: u64, rng: &mut fastrand::Rng) {
+        let mut set = RangeSet::new();
+        let mut flags = [false; UNIVERSE];
+        for _ in 0..200 {
+            let start = rng.usize(..UNIVERSE);
+            let end = rng.usize(start..UNIVERSE);
+            let range = Range { start: base + start as u64, end: base + end as u64 };
+            let insert = rng.bool();
+            if insert {
+                set.insert(range);
+            } else {
+                set.remove(range);
+            }
+            flags[start..=end].fill(insert);
+
+            for (offset, &flag) in flags.iter().enumerate() {
+                assert_eq!(set.contains(base + offset as u64), flag, "{} after {:?}", base + offset as u64, set);
+            }
+            assert_eq!(set.total_length(), flags.iter().filter(|&&flag| flag).count() as u128);
+            let ranges = set.iter().copied().collect::<Vec<Range>>();
+            assert!(ranges.iter().all(|range| !range.is_empty()), "{:?}", set);
+            for pair in ranges.windows(2) {
+                // Sorted, disjoint and never adjacent either.
+                assert!(pair[0].end + 1 < pair[1].start, "{:?}", set);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_a_flag_per_id() {
+        let mut rng = fastrand::Rng::with_seed(43);
+        for base in [0, 1 << 32, u64::MAX - UNIVERSE as u64 + 1] {
+            for _ in 0..50 {
+                check_against_flags(base, &mut rng);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_every_id() {
+        let everything = [Range { start: 0, end: u64::MAX }].into_iter().collect::<RangeSet>();
+        assert_eq!(everything.total_length(), u64::MAX as u128 + 1);
+    }
+}
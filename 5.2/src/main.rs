@@ -1,104 +1,165 @@
+mod parse;
+mod range;
+mod range_set;
+mod stats;
+
+use std::io::BufRead;
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::cmp::{Ord, Ordering, PartialOrd, PartialEq, Eq};
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-struct Range {
-    start: u64,
-    end: u64,
+use parse::{read_database, ParseError};
+use range::{MergePolicy, Range};
+use range_set::RangeSet;
+use stats::Format;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+    SymmetricDifference,
+    Complement,
 }
 
-impl FromStr for Range {
-    type Err = std::num::ParseIntError;
+impl FromStr for Operation {
+    type Err = anyhow::Error;
+
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let strs = s.split("-").collect::<Vec<&str>>();
-        let start = strs[0].parse::<u64>()?;
-        let end = strs[1].parse::<u64>()?;
-        Ok(Range { start, end })
+        match s {
+            "union" => Ok(Operation::Union),
+            "intersection" => Ok(Operation::Intersection),
+            "difference" => Ok(Operation::Difference),
+            "symmetric-difference" => Ok(Operation::SymmetricDifference),
+            "complement" => Ok(Operation::Complement),
+            _ => Err(anyhow::anyhow!("Invalid operation: {}", s)),
+        }
     }
 }
 
-impl Ord for Range {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.start.cmp(&other.start)
-    }
+#[derive(Debug)]
+struct Options {
+    operation: Option<Operation>,
+    /// The second range file for operations between two sets.
+    with: Option<PathBuf>,
+    /// Range the complement is taken within.
+    bounds: Option<Range>,
+    merge: MergePolicy,
+    /// Whether to report gaps and coverage statistics instead of the joined ranges.
+    stats: bool,
+    /// Window to report the covered length of in the statistics.
+    window: Option<Range>,
+    /// How many of the longest gaps to list.
+    largest: usize,
+    format: Format,
 }
 
-impl PartialOrd for Range {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.start.cmp(&other.start))
+fn parse_args() -> Options {
+    let mut options = Options {
+        operation: None,
+        with: None,
+        bounds: None,
+        merge: MergePolicy::default(),
+        stats: false,
+        window: None,
+        largest: 5,
+        format: Format::default(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--op" => options.operation = Some(Operation::from_str(&value()).unwrap()),
+            "--with" => options.with = Some(value().into()),
+            "--bounds" => options.bounds = Some(Range::from_str(&value()).unwrap()),
+            "--merge" => options.merge = MergePolicy::from_str(&value()).unwrap(),
+            "--stats" => options.stats = true,
+            "--window" => options.window = Some(Range::from_str(&value()).unwrap()),
+            "--largest" => options.largest = value().parse::<usize>().unwrap(),
+            "--format" => options.format = Format::from_str(&value()).unwrap(),
+            _ => panic!("unknown argument: {}", arg),
+        }
     }
+    options
 }
 
-impl Range {
-    fn does_intersect(&self, other: &Range) -> bool {
-        (self.start <= other.start && self.end >= other.start) || 
-        (self.start <= other.end && self.end >= other.end)
+fn main() {
+    let options = parse_args();
+    let mut ranges = match read_ranges(get_lines()) {
+        Ok(ranges) => ranges,
+        Err(e) => {
+            eprintln!("invalid database {}", e);
+            std::process::exit(1);
+        }
+    };
+    ranges.sort();
+    let mut joined_ranges = RangeSet::with_policy(options.merge);
+    joined_ranges.extend(ranges.iter().copied());
+
+    if let Some(operation) = options.operation {
+        let result = apply(operation, &joined_ranges, &options);
+        // One `start-end` per line, so the result can feed another operation.
+        for range in &result {
+            println!("{}", range);
+        }
+        println!("total length: {}", result.total_length());
+        return;
     }
 
-    fn join(&self, other: &Range) -> Range {
-        Range {
-            start: self.start.min(other.start),
-            end: self.end.max(other.end),
+    if options.stats {
+        let stats = stats::stats(&joined_ranges, options.window, options.largest);
+        match options.format {
+            Format::Table => print!("{}", stats.to_table()),
+            Format::Json => println!("{}", stats.to_json()),
         }
+        return;
     }
-}
 
-fn main() {
-    let mut lines = get_lines();
-    let mut ranges = get_ranges(&mut lines);
-    ranges.sort();
-    let joined_ranges = join_ranges(&ranges);
     println!("ranges: {:?}", ranges);
-    println!("joined ranges: {:?}", joined_ranges);
-    let mut total_length = 0;
-    for range in joined_ranges {
-        total_length += range.end - range.start + 1;
+    println!("joined ranges: {:?}", joined_ranges.iter().collect::<Vec<&Range>>());
+    // Every input range lies inside exactly one joined range, the one holding its start.
+    let mut absorbed = vec![0; joined_ranges.iter().len()];
+    for range in &ranges {
+        absorbed[joined_ranges.index_of(range.start).unwrap()] += 1;
+    }
+    for (range, count) in joined_ranges.iter().zip(absorbed) {
+        println!("{}: {} input ranges", range, count);
     }
-    println!("total length: {}", total_length);
+    println!("total length: {}", joined_ranges.total_length());
 }
 
-fn join_ranges(ranges: &Vec<Range>) -> Vec<Range> {
-    let mut joined_ranges = Vec::new();
-    let mut current_range_option: Option<Range> = None;
-
-    for range in ranges.iter() {
-        match current_range_option {
-            Some(current_range) if current_range.does_intersect(range) => {
-                println!("joining ranges: {:?} and {:?}", current_range, range);
-                current_range_option = Some(current_range.join(range));
-            }
-            Some(current_range) => {
-                println!("NOT joining ranges: {:?} and {:?}", current_range, range);
-                joined_ranges.push(current_range);
-                current_range_option = Some(*range);
-            }
-            None => {
-                current_range_option = Some(*range);
-            }
-        }
+fn apply(operation: Operation, ranges: &RangeSet, options: &Options) -> RangeSet {
+    if operation == Operation::Complement {
+        let bounds = options.bounds.expect("complement needs --bounds start-end");
+        return ranges.complement(bounds);
     }
-    
-    if let Some(current_range) = current_range_option {
-        joined_ranges.push(current_range);
+    let path = options.with.as_ref().expect("operation needs --with file");
+    let file = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+    let other_ranges = read_ranges(file.lines().map(|line| line.unwrap())).unwrap_or_else(|e| {
+        eprintln!("invalid database {} {}", path.display(), e);
+        std::process::exit(1);
+    });
+    let mut other = RangeSet::with_policy(options.merge);
+    other.extend(other_ranges);
+    match operation {
+        Operation::Union => ranges.union(&other),
+        Operation::Intersection => ranges.intersection(&other),
+        Operation::Difference => ranges.difference(&other),
+        Operation::SymmetricDifference => ranges.symmetric_difference(&other),
+        Operation::Complement => unreachable!(),
     }
-
-    joined_ranges
 }
 
-fn get_ranges(lines: &mut impl Iterator<Item = String>) -> Vec<Range> {
-    let mut ranges = Vec::new();
-    for line in lines {
-        let range_result = Range::from_str(&line);
-        match range_result {
-            Ok(range) => ranges.push(range),
-            Err(_) => return ranges
-        }
+/// The ranges of a database, which may leave out the IDs section. IDs are still checked so
+/// that a malformed line anywhere is reported.
+fn read_ranges(lines: impl Iterator<Item = String>) -> Result<Vec<Range>, ParseError> {
+    let database = read_database(lines)?;
+    for id in database.ids {
+        id?;
     }
-    panic!("Oops all ranges!");
+    Ok(database.ranges.into_iter().map(|(_, range)| range).collect())
 }
 
 fn get_lines() -> impl Iterator<Item = String> {
     let stdin = std::io::stdin();
-    let lines = stdin.lines().map(|line| line.unwrap());
-    lines
-}
\ No newline at end of file
+    stdin.lines().map(|line| line.unwrap())
+}
@@ -0,0 +1,118 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+/// An inclusive range of ingredient IDs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Range {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl FromStr for Range {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-').ok_or_else(|| anyhow!("Invalid range: {}", s))?;
+        let bound = |b: &str| b.parse::<u64>().map_err(|_| anyhow!("Invalid range bound {:?} in {}", b, s));
+        let range = Range { start: bound(start)?, end: bound(end)? };
+        if range.is_empty() {
+            return Err(anyhow!("Reversed range: {}", s));
+        }
+        Ok(range)
+    }
+}
+
+/// Which ranges are joined into one when merging.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MergePolicy {
+    /// Only ranges that share at least one ID.
+    Overlapping,
+    /// Also ranges where one starts right after the other ends, such as `3-5` and `6-8`.
+    #[default]
+    Adjacent,
+}
+
+impl FromStr for MergePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overlapping" => Ok(MergePolicy::Overlapping),
+            "adjacent" => Ok(MergePolicy::Adjacent),
+            _ => Err(anyhow!("Invalid merge policy: {}", s)),
+        }
+    }
+}
+
+impl MergePolicy {
+    pub fn joins(&self, a: &Range, b: &Range) -> bool {
+        match self {
+            MergePolicy::Overlapping => a.does_intersect(b),
+            MergePolicy::Adjacent => a.touches(b),
+        }
+    }
+
+    /// Whether `range` ends early enough that nothing starting at `start` or later can join it.
+    pub fn ends_before(&self, range: &Range, start: u64) -> bool {
+        match self {
+            MergePolicy::Overlapping => range.end < start,
+            MergePolicy::Adjacent => range.end.saturating_add(1) < start,
+        }
+    }
+}
+
+impl std::fmt::Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl Ord for Range {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.start.cmp(&other.start)
+    }
+}
+
+impl PartialOrd for Range {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Range {
+    pub fn contains(&self, value: u64) -> bool {
+        value >= self.start && value <= self.end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start > self.end
+    }
+
+    /// Number of IDs in the range, which is one more than `u64::MAX` for `0-18446744073709551615`.
+    pub fn len(&self) -> u128 {
+        if self.is_empty() {
+            0
+        } else {
+            (self.end - self.start) as u128 + 1
+        }
+    }
+
+    pub fn does_intersect(&self, other: &Range) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// Whether the two ranges overlap or one starts right after the other ends, so that
+    /// together they cover a single run of IDs.
+    pub fn touches(&self, other: &Range) -> bool {
+        let (first, second) = if self.start <= other.start { (self, other) } else { (other, self) };
+        first.does_intersect(second) || first.end.checked_add(1) == Some(second.start)
+    }
+
+    pub fn join(&self, other: &Range) -> Range {
+        Range {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use crate::range::{MergePolicy, Range};
+
+/// A set of IDs stored as sorted, disjoint ranges. Ranges are merged as they are inserted
+/// according to the set's policy; with the default policy, which also merges ranges that
+/// touch, every set has exactly one representation.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RangeSet {
+    ranges: Vec<Range>,
+    policy: MergePolicy,
+}
+
+impl RangeSet {
+    pub fn new() -> Self {
+        RangeSet::default()
+    }
+
+    pub fn with_policy(policy: MergePolicy) -> Self {
+        RangeSet { ranges: Vec::new(), policy }
+    }
+
+    /// Adds every ID in `range`, joining it with any stored ranges the policy joins it to.
+    pub fn insert(&mut self, range: Range) {
+        if range.is_empty() {
+            return;
+        }
+        // Stored ranges before `first` end too early to join `range`.
+        let first = self.ranges.partition_point(|r| self.policy.ends_before(r, range.start));
+        let mut joined = range;
+        let mut last = first;
+        while last < self.ranges.len() && self.policy.joins(&self.ranges[last], &joined) {
+            joined = joined.join(&self.ranges[last]);
+            last += 1;
+        }
+        self.ranges.splice(first..last, [joined]);
+    }
+
+    /// Removes every ID in `range`, splitting a stored range that straddles it.
+    pub fn remove(&mut self, range: Range) {
+        if range.is_empty() {
+            return;
+        }
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        if first >= last {
+            return;
+        }
+        let (head, tail) = (self.ranges[first], self.ranges[last - 1]);
+        let mut kept = Vec::new();
+        if head.start < range.start {
+            kept.push(Range { start: head.start, end: range.start - 1 });
+        }
+        if tail.end > range.end {
+            kept.push(Range { start: range.end + 1, end: tail.end });
+        }
+        self.ranges.splice(first..last, kept);
+    }
+
+    /// Binary searches for the one range that could hold `value`.
+    pub fn contains(&self, value: u64) -> bool {
+        self.index_of(value).is_some()
+    }
+
+    /// Position in `iter` order of the range holding `value`.
+    pub fn index_of(&self, value: u64) -> Option<usize> {
+        let index = self.ranges.partition_point(|r| r.end < value);
+        self.ranges.get(index).is_some_and(|r| r.contains(value)).then_some(index)
+    }
+
+    /// The disjoint ranges in ascending order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Range> {
+        self.ranges.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of IDs in the set.
+    pub fn total_length(&self) -> u128 {
+        self.ranges.iter().map(Range::len).sum()
+    }
+
+    /// Number of IDs within `bounds` that the set holds.
+    pub fn covered_within(&self, bounds: Range) -> u128 {
+        let first = self.ranges.partition_point(|r| r.end < bounds.start);
+        let last = self.ranges.partition_point(|r| r.start <= bounds.end);
+        let clipped = self.ranges[first..last.max(first)].iter().map(|r| {
+            Range { start: r.start.max(bounds.start), end: r.end.min(bounds.end) }.len()
+        });
+        clipped.sum()
+    }
+
+    /// Joins ranges that arrive in order of their start into a set with this set's policy.
+    fn join_sorted(&self, ranges: impl Iterator<Item = Range>) -> RangeSet {
+        let mut joined: Vec<Range> = Vec::new();
+        for range in ranges.filter(|r| !r.is_empty()) {
+            match joined.last_mut() {
+                Some(current) if self.policy.joins(current, &range) => *current = current.join(&range),
+                _ => joined.push(range),
+            }
+        }
+        RangeSet { ranges: joined, policy: self.policy }
+    }
+
+    /// IDs in either set.
+    pub fn union(&self, other: &RangeSet) -> RangeSet {
+        let mut ranges = self.ranges.iter().chain(&other.ranges).copied().collect::<Vec<Range>>();
+        ranges.sort();
+        self.join_sorted(ranges.into_iter())
+    }
+
+    /// IDs in both sets, found by walking the two range lists side by side.
+    pub fn intersection(&self, other: &RangeSet) -> RangeSet {
+        let mut common = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (self.ranges[i], other.ranges[j]);
+            if a.does_intersect(&b) {
+                common.push(Range { start: a.start.max(b.start), end: a.end.min(b.end) });
+            }
+            // Whichever range ends first cannot meet anything further along the other list.
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        self.join_sorted(common.into_iter())
+    }
+
+    /// IDs in this set but not in `other`.
+    pub fn difference(&self, other: &RangeSet) -> RangeSet {
+        self.intersection(&other.complement(Range { start: 0, end: u64::MAX }))
+    }
+
+    /// IDs in exactly one of the two sets.
+    pub fn symmetric_difference(&self, other: &RangeSet) -> RangeSet {
+        self.difference(other).union(&other.difference(self))
+    }
+
+    /// IDs within `bounds` that are not in the set.
+    pub fn complement(&self, bounds: Range) -> RangeSet {
+        let mut gaps = Vec::new();
+        let mut next = Some(bounds.start);
+        for range in self.ranges.iter().filter(|r| r.does_intersect(&bounds)) {
+            let Some(start) = next else { break };
+            if range.start > start {
+                gaps.push(Range { start, end: range.start - 1 });
+            }
+            next = range.end.checked_add(1);
+        }
+        if let Some(start) = next {
+            gaps.push(Range { start, end: bounds.end });
+        }
+        self.join_sorted(gaps.into_iter())
+    }
+}
+
+impl FromIterator<Range> for RangeSet {
+    fn from_iter<I: IntoIterator<Item = Range>>(ranges: I) -> Self {
+        let mut set = RangeSet::new();
+        set.extend(ranges);
+        set
+    }
+}
+
+impl Extend<Range> for RangeSet {
+    fn extend<I: IntoIterator<Item = Range>>(&mut self, ranges: I) {
+        for range in ranges {
+            self.insert(range);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a RangeSet {
+    type Item = &'a Range;
+    type IntoIter = std::slice::Iter<'a, Range>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeSet;
+    use crate::range::{MergePolicy, Range};
+
+    const UNIVERSE: usize = 64;
+    const BASES: [u64; 3] = [0, 1 << 32, u64::MAX - UNIVERSE as u64 + 1];
+    const POLICIES: [MergePolicy; 2] = [MergePolicy::Adjacent, MergePolicy::Overlapping];
+
+    /// One flag per ID of `base..base + 64`, set for the IDs a set should hold.
+    type Flags = [bool; UNIVERSE];
+
+    /// Checks that `set` holds exactly the flagged IDs, as sorted, disjoint ranges that its
+    /// policy would not join.
+    fn assert_matches(set: &RangeSet, flags: &Flags, base: u64) {
+        for (offset, &flag) in flags.iter().enumerate() {
+            assert_eq!(set.contains(base + offset as u64), flag, "{} in {:?}", base + offset as u64, set);
+        }
+        assert_eq!(set.total_length(), flags.iter().filter(|&&flag| flag).count() as u128);
+        let ranges = set.iter().copied().collect::<Vec<Range>>();
+        assert!(ranges.iter().all(|range| !range.is_empty()), "{:?}", set);
+        for pair in ranges.windows(2) {
+            // Sorted, disjoint and, under the default policy, never adjacent either.
+            assert!(set.policy.ends_before(&pair[0], pair[1].start), "{:?}", set);
+        }
+    }
+
+    /// Inserts or removes a random run of IDs within `base..base + 64` in both `set` and `flags`.
+    fn random_edit(set: &mut RangeSet, flags: &mut Flags, base: u64, rng: &mut fastrand::Rng) {
+        let start = rng.usize(..UNIVERSE);
+        let end = rng.usize(start..UNIVERSE);
+        let range = Range { start: base + start as u64, end: base + end as u64 };
+        let insert = rng.bool();
+        if insert {
+            set.insert(range);
+        } else {
+            set.remove(range);
+        }
+        flags[start..=end].fill(insert);
+    }
+
+    fn random_set(policy: MergePolicy, base: u64, rng: &mut fastrand::Rng) -> (RangeSet, Flags) {
+        let (mut set, mut flags) = (RangeSet::with_policy(policy), [false; UNIVERSE]);
+        for _ in 0..rng.usize(..20) {
+            random_edit(&mut set, &mut flags, base, rng);
+        }
+        (set, flags)
+    }
+
+    fn combine(a: &Flags, b: &Flags, op: fn(bool, bool) -> bool) -> Flags {
+        std::array::from_fn(|i| op(a[i], b[i]))
+    }
+
+    #[test]
+    fn matches_a_flag_per_id() {
+        let mut rng = fastrand::Rng::with_seed(43);
+        for base in BASES {
+            for policy in POLICIES {
+                for _ in 0..50 {
+                    let (mut set, mut flags) = (RangeSet::with_policy(policy), [false; UNIVERSE]);
+                    for _ in 0..200 {
+                        random_edit(&mut set, &mut flags, base, &mut rng);
+                        assert_matches(&set, &flags, base);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_algebra_matches_flags() {
+        let mut rng = fastrand::Rng::with_seed(45);
+        for base in BASES {
+            for policy in POLICIES {
+                for _ in 0..500 {
+                    let (a, a_flags) = random_set(policy, base, &mut rng);
+                    let (b, b_flags) = random_set(policy, base, &mut rng);
+                    assert_matches(&a.union(&b), &combine(&a_flags, &b_flags, |x, y| x || y), base);
+                    assert_matches(&a.intersection(&b), &combine(&a_flags, &b_flags, |x, y| x && y), base);
+                    assert_matches(&a.difference(&b), &combine(&a_flags, &b_flags, |x, y| x && !y), base);
+                    assert_matches(&a.symmetric_difference(&b), &combine(&a_flags, &b_flags, |x, y| x != y), base);
+
+                    let start = rng.usize(..UNIVERSE);
+                    let end = rng.usize(start..UNIVERSE);
+                    let bounds = Range { start: base + start as u64, end: base + end as u64 };
+                    let outside = std::array::from_fn(|i| (start..=end).contains(&i) && !a_flags[i]);
+                    assert_matches(&a.complement(bounds), &outside, base);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn counts_every_id() {
+        let everything = [Range { start: 0, end: u64::MAX }].into_iter().collect::<RangeSet>();
+        assert_eq!(everything.total_length(), u64::MAX as u128 + 1);
+    }
+}
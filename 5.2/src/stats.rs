@@ -0,0 +1,153 @@
+use std::str::FromStr;
+
+use crate::range::Range;
+use crate::range_set::RangeSet;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Table,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            _ => Err(anyhow::anyhow!("Invalid format: {}", s)),
+        }
+    }
+}
+
+/// Merged ranges whose length is between `min_length` and `max_length`, inclusive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bucket {
+    pub min_length: u128,
+    pub max_length: u128,
+    pub count: usize,
+}
+
+#[derive(Debug)]
+pub struct Stats {
+    pub range_count: usize,
+    pub total_length: u128,
+    /// Uncovered runs between consecutive merged ranges, in order.
+    pub gaps: Vec<Range>,
+    /// The longest gaps, longest first.
+    pub largest_gaps: Vec<Range>,
+    /// A query window and how many of its IDs are covered.
+    pub window: Option<(Range, u128)>,
+    /// Merged range lengths in power-of-two buckets, from the shortest to the longest range.
+    pub histogram: Vec<Bucket>,
+}
+
+/// Gathers coverage statistics for the merged ranges of `set`.
+pub fn stats(set: &RangeSet, window: Option<Range>, largest: usize) -> Stats {
+    let gaps = match (set.iter().next(), set.iter().last()) {
+        (Some(first), Some(last)) => set.complement(Range { start: first.start, end: last.end }).iter().copied().collect(),
+        _ => Vec::new(),
+    };
+    let mut largest_gaps = gaps.clone();
+    largest_gaps.sort_by_key(|gap| (std::cmp::Reverse(gap.len()), gap.start));
+    largest_gaps.truncate(largest);
+
+    // Bucket `k` holds lengths from 2^k to 2^(k+1) - 1. Stored ranges are never empty, and
+    // only the range of every ID reaches bucket 64.
+    let bucket = |range: &Range| range.len().ilog2() as usize;
+    let mut histogram = Vec::new();
+    if let (Some(low), Some(high)) = (set.iter().map(bucket).min(), set.iter().map(bucket).max()) {
+        for k in low..=high {
+            let max_length = ((1u128 << (k + 1)) - 1).min(Range { start: 0, end: u64::MAX }.len());
+            histogram.push(Bucket { min_length: 1 << k, max_length, count: 0 });
+        }
+        for range in set {
+            histogram[bucket(range) - low].count += 1;
+        }
+    }
+
+    Stats {
+        range_count: set.iter().len(),
+        total_length: set.total_length(),
+        gaps,
+        largest_gaps,
+        window: window.map(|bounds| (bounds, set.covered_within(bounds))),
+        histogram,
+    }
+}
+
+fn range_json(range: &Range) -> String {
+    format!("{{\"start\": {}, \"end\": {}, \"length\": {}}}", range.start, range.end, range.len())
+}
+
+impl Stats {
+    pub fn to_table(&self) -> String {
+        let mut table = String::new();
+        table.push_str(&format!("merged ranges: {}\n", self.range_count));
+        table.push_str(&format!("total length: {}\n", self.total_length));
+        table.push_str(&format!("gaps: {}\n", self.gaps.len()));
+        for gap in &self.gaps {
+            table.push_str(&format!("  {:<35} {:>16}\n", gap.to_string(), gap.len()));
+        }
+        table.push_str("largest gaps:\n");
+        for gap in &self.largest_gaps {
+            table.push_str(&format!("  {:<35} {:>16}\n", gap.to_string(), gap.len()));
+        }
+        if let Some((bounds, covered)) = self.window {
+            table.push_str(&format!("window {}: {} of {} covered\n", bounds, covered, bounds.len()));
+        }
+        table.push_str("length histogram:\n");
+        let widest = self.histogram.iter().map(|bucket| bucket.count).max().unwrap_or(0).max(1);
+        for bucket in &self.histogram {
+            let lengths = format!("{}-{}", bucket.min_length, bucket.max_length);
+            let bar = "#".repeat((bucket.count * 40).div_ceil(widest));
+            table.push_str(&format!("  {:<35} {:>6} {}\n", lengths, bucket.count, bar));
+        }
+        table
+    }
+
+    pub fn to_json(&self) -> String {
+        let ranges = |ranges: &[Range]| ranges.iter().map(range_json).collect::<Vec<String>>().join(", ");
+        let window = match self.window {
+            Some((bounds, covered)) => format!(
+                "{{\"start\": {}, \"end\": {}, \"covered\": {}}}",
+                bounds.start, bounds.end, covered
+            ),
+            None => "null".to_string(),
+        };
+        let histogram = self.histogram.iter().map(|bucket| {
+            format!(
+                "{{\"min_length\": {}, \"max_length\": {}, \"count\": {}}}",
+                bucket.min_length, bucket.max_length, bucket.count
+            )
+        });
+        format!(
+            "{{\"merged_ranges\": {}, \"total_length\": {}, \"gaps\": [{}], \"largest_gaps\": [{}], \"window\": {}, \"histogram\": [{}]}}",
+            self.range_count,
+            self.total_length,
+            ranges(&self.gaps),
+            ranges(&self.largest_gaps),
+            window,
+            histogram.collect::<Vec<String>>().join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stats, Bucket};
+    use crate::range::Range;
+    use crate::range_set::RangeSet;
+
+    #[test]
+    fn buckets_the_range_of_every_id() {
+        let everything = Range { start: 0, end: u64::MAX };
+        let stats = stats(&[everything].into_iter().collect::<RangeSet>(), Some(everything), 5);
+        let length = u64::MAX as u128 + 1;
+        assert_eq!(stats.total_length, length);
+        assert_eq!(stats.window, Some((everything, length)));
+        assert_eq!(stats.histogram, vec![Bucket { min_length: length, max_length: length, count: 1 }]);
+    }
+}